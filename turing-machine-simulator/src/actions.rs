@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{gamepad, settings::Settings};
+
+///something the player can do in game, whichever key it is bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action{
+    MoveLeft,
+    MoveRight,
+    Erase,
+    Run,
+    Step,
+    StepBack,
+    SpeedUp,
+    SpeedDown,
+    PreviousTape,
+    NextTape,
+    ExploreTree,
+    ExportDiagram,
+    StateDiagram,
+    TransitionTable,
+    Exit,
+}
+
+impl Action{
+    pub const ALL: [Action; 15] = [Action::MoveLeft, Action::MoveRight, Action::Erase, Action::Run, Action::Step, Action::StepBack, Action::SpeedUp, Action::SpeedDown, Action::PreviousTape, Action::NextTape, Action::ExploreTree, Action::ExportDiagram, Action::StateDiagram, Action::TransitionTable, Action::Exit];
+
+    pub fn name(self) -> &'static str{
+        match self{
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Erase => "Erase",
+            Action::Run => "Run / Pause",
+            Action::Step => "Step",
+            Action::StepBack => "Step Back",
+            Action::SpeedUp => "Speed Up",
+            Action::SpeedDown => "Slow Down",
+            Action::PreviousTape => "Previous Tape",
+            Action::NextTape => "Next Tape",
+            Action::ExploreTree => "Computation Tree",
+            Action::ExportDiagram => "Export Diagram",
+            Action::StateDiagram => "State Diagram",
+            Action::TransitionTable => "Transition Table",
+            Action::Exit => "Exit",
+        }
+    }
+
+    fn default_key(self) -> KeyBinding{
+        let (key, shift) = match self{
+            Action::MoveLeft => (KeyCode::ArrowLeft, false),
+            Action::MoveRight => (KeyCode::ArrowRight, false),
+            Action::Erase => (KeyCode::Backspace, false),
+            Action::Run => (KeyCode::Tab, false),
+            Action::Step => (KeyCode::Enter, false),
+            Action::StepBack => (KeyCode::Enter, true),
+            Action::SpeedUp => (KeyCode::ArrowUp, false),
+            Action::SpeedDown => (KeyCode::ArrowDown, false),
+            Action::PreviousTape => (KeyCode::PageUp, false),
+            Action::NextTape => (KeyCode::PageDown, false),
+            Action::ExploreTree => (KeyCode::F2, false),
+            Action::ExportDiagram => (KeyCode::F3, false),
+            Action::StateDiagram => (KeyCode::F4, false),
+            Action::TransitionTable => (KeyCode::F5, false),
+            Action::Exit => (KeyCode::Escape, false),
+        };
+        KeyBinding{key, shift}
+    }
+
+    ///gamepad buttons aren't rebindable
+    fn gamepad_button(self) -> GamepadButton{
+        match self{
+            Action::MoveLeft => GamepadButton::DPadLeft,
+            Action::MoveRight => GamepadButton::DPadRight,
+            Action::Erase => GamepadButton::West,
+            Action::Run => GamepadButton::Select,
+            Action::Step => GamepadButton::RightTrigger,
+            Action::StepBack => GamepadButton::LeftTrigger,
+            Action::SpeedUp => GamepadButton::DPadUp,
+            Action::SpeedDown => GamepadButton::DPadDown,
+            Action::PreviousTape => GamepadButton::LeftTrigger2,
+            Action::NextTape => GamepadButton::RightTrigger2,
+            Action::ExploreTree => GamepadButton::North,
+            Action::ExportDiagram => GamepadButton::LeftThumb,
+            Action::StateDiagram => GamepadButton::RightThumb,
+            Action::TransitionTable => GamepadButton::Mode,
+            Action::Exit => GamepadButton::Start,
+        }
+    }
+}
+
+///a key, optionally held together with shift
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding{
+    pub key: KeyCode,
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl KeyBinding{
+    ///what the binding looks like in the controls menu
+    pub fn label(&self) -> String{
+        let key = format!("{:?}", self.key);
+        let key = key.strip_prefix("Key").or_else(|| key.strip_prefix("Digit")).unwrap_or(&key);
+        if self.shift {format!("Shift+{}", key)} else {key.to_string()}
+    }
+}
+
+///the key each action is bound to. actions missing from the settings file use their default key
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(BTreeMap<Action, KeyBinding>);
+
+impl Bindings{
+    pub fn get(&self, action: Action) -> KeyBinding{
+        self.0.get(&action).copied().unwrap_or_else(|| action.default_key())
+    }
+
+    ///binds `action` to `binding`, giving its old key to any action that had `binding`
+    pub fn set(&mut self, action: Action, binding: KeyBinding){
+        let old = self.get(action);
+        for other in Action::ALL{
+            if other != action && self.get(other) == binding{
+                self.0.insert(other, old);
+            }
+        }
+        self.0.insert(action, binding);
+    }
+
+    ///whether any action uses `key`, with or without shift
+    pub fn is_bound(&self, key: KeyCode) -> bool{
+        Action::ALL.iter().any(|&action| self.get(action).key == key)
+    }
+
+    pub fn reset(&mut self){
+        self.0.clear();
+    }
+}
+
+///reads actions from the keyboard, using the player's bindings, and from gamepads
+#[derive(SystemParam)]
+pub struct Actions<'w, 's>{
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+    settings: Res<'w, Settings>,
+}
+
+impl Actions<'_, '_>{
+    fn shift(&self) -> bool{
+        self.keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool{
+        let binding = self.settings.bindings.get(action);
+        (self.keys.just_pressed(binding.key) && self.shift() == binding.shift)
+            || gamepad::just_pressed(&self.gamepads, action.gamepad_button())
+    }
+}
@@ -0,0 +1,129 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+use bevy::{asset::{io::{file::FileAssetReader, AssetReaderError}, AssetLoadError, AssetLoadFailedEvent, AssetPath}, prelude::*, text::Update2dText, ui::UiSystem};
+
+use crate::toast::{show_toast, Toast};
+
+///directory under `assets/` holding every sound
+const AUDIO_DIRECTORY: &str = "audio";
+///default value of `AssetPlugin::file_path`
+const ASSET_DIRECTORY: &str = "assets";
+
+//Manifest
+pub const MENU_SOUND_FILES: [&str; 3] = ["menu-move.mp3", "menu-select.mp3", "menu-back.mp3"];
+pub const GAME_SOUND_FILES: [&str; 5] = ["game-move.mp3", "game-cant-move.mp3", "game-select.mp3", "game-write.mp3", "game-delete.mp3"];
+pub const FONT_FILE: &str = "dos_font.ttf";
+
+///handles to every asset in the manifest, keyed by file name
+#[derive(Resource)]
+pub struct AssetRegistry{
+    sounds: HashMap<&'static str, Handle<AudioSource>>,
+    pub font: Handle<Font>,
+}
+
+impl AssetRegistry{
+    ///the handle of a sound from the manifest
+    pub fn sound(&self, file_name: &str) -> Handle<AudioSource>{
+        self.sounds.get(file_name)
+            .unwrap_or_else(|| panic!("{} is not in the asset manifest", file_name))
+            .clone()
+    }
+}
+
+///path of a sound relative to the asset directory
+fn sound_path(file_name: &str) -> PathBuf{
+    Path::new(AUDIO_DIRECTORY).join(file_name)
+}
+
+///every path in the manifest, relative to the asset directory
+fn manifest() -> Vec<PathBuf>{
+    MENU_SOUND_FILES.iter()
+        .chain(GAME_SOUND_FILES.iter())
+        .map(|file_name| sound_path(file_name))
+        .chain([PathBuf::from(FONT_FILE)])
+        .collect()
+}
+
+pub struct AssetManifestPlugin;
+
+impl Plugin for AssetManifestPlugin{
+    fn build(&self, app: &mut App){
+        app
+        .add_systems(
+            PreStartup,
+            load_assets
+        )
+        .add_systems(
+            Startup,
+            report_missing_assets
+        )
+        .add_systems(
+            Update,
+            (
+                report_failed_loads::<AudioSource>,
+                report_failed_loads::<Font>,
+            )
+        )
+        .add_systems(
+            PostUpdate,
+            apply_font.before(UiSystem::Prepare).before(Update2dText)
+        );
+    }
+}
+
+///starts loading every asset in the manifest
+fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+){
+    let sounds = MENU_SOUND_FILES.iter()
+        .chain(GAME_SOUND_FILES.iter())
+        .map(|&file_name| (file_name, asset_server.load(AssetPath::from(sound_path(file_name)))))
+        .collect();
+    commands.insert_resource(AssetRegistry{
+        sounds,
+        font: asset_server.load(FONT_FILE),
+    });
+}
+
+///gives new text the manifest's font, before it is laid out
+fn apply_font(
+    registry: Res<AssetRegistry>,
+    mut texts: Query<&mut TextFont, Added<TextFont>>,
+){
+    for mut text_font in &mut texts{
+        text_font.font = registry.font.clone();
+    }
+}
+
+///checks that every file in the manifest is on disk before anything tries to play it
+fn report_missing_assets(
+    mut commands: Commands,
+    toasts: Query<Entity, With<Toast>>,
+){
+    let root = FileAssetReader::get_base_path().join(ASSET_DIRECTORY);
+    let missing: Vec<String> = manifest().into_iter()
+        .filter(|path| !root.join(path).is_file())
+        .map(|path| path.display().to_string())
+        .collect();
+    if missing.is_empty(){
+        return;
+    }
+    error!("missing assets in {}: {}", root.display(), missing.join(", "));
+    show_toast(&mut commands, &toasts, format!("Missing assets: {}", missing.join(", ")));
+}
+
+///reports assets that exist but couldn't be loaded
+fn report_failed_loads<A: Asset>(
+    mut commands: Commands,
+    mut failures: EventReader<AssetLoadFailedEvent<A>>,
+    toasts: Query<Entity, With<Toast>>,
+){
+    for failure in failures.read(){
+        //missing files were already reported at startup
+        if matches!(failure.error, AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_))){
+            continue;
+        }
+        error!("failed to load {}: {}", failure.path, failure.error);
+        show_toast(&mut commands, &toasts, format!("Couldn't load {}", failure.path));
+    }
+}
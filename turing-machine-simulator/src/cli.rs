@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: turing-machine-simulator [--data-dir <path>] [--config-dir <path>] [--machine <file.tm>] [--import <file>] [--export <save name> <file.jff|file.dot|file.svg>]";
+
+///options given on the command line
+#[derive(Default, Debug)]
+pub struct Args{
+    ///overrides where saves are kept
+    pub data_dir: Option<PathBuf>,
+    ///overrides where settings are kept
+    pub config_dir: Option<PathBuf>,
+    ///machine description to open straight into the sandbox
+    pub machine: Option<PathBuf>,
+    ///machine from another simulator to put in a new save slot and open
+    pub import: Option<PathBuf>,
+    ///save whose machine or state diagram is written to a file, instead of opening the game
+    pub export: Option<(String, PathBuf)>,
+}
+
+impl Args{
+    ///parses the process's arguments, exiting with a usage message if they're invalid
+    pub fn parse() -> Self{
+        match Self::parse_from(std::env::args().skip(1)){
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("{}\n{}", e, USAGE);
+                std::process::exit(2);
+            },
+        }
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self, String>{
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next(){
+            match arg.as_str(){
+                "--data-dir" => parsed.data_dir = Some(args.next().ok_or("--data-dir needs a path")?.into()),
+                "--config-dir" => parsed.config_dir = Some(args.next().ok_or("--config-dir needs a path")?.into()),
+                "--machine" => parsed.machine = Some(args.next().ok_or("--machine needs a path")?.into()),
+                "--import" => parsed.import = Some(args.next().ok_or("--import needs a path")?.into()),
+                "--export" => {
+                    let slot = args.next().ok_or("--export needs a save name and a path")?;
+                    let path = args.next().ok_or("--export needs a path after the save name")?;
+                    parsed.export = Some((slot, path.into()));
+                },
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                },
+                _ => return Err(format!("unknown argument \"{}\"", arg)),
+            }
+        }
+        //both open straight into the game, with different machines
+        if parsed.machine.is_some() && parsed.import.is_some(){
+            return Err("--machine and --import can't be used together".to_string());
+        }
+        //exporting writes a file and quits without opening the game
+        if parsed.export.is_some() && (parsed.machine.is_some() || parsed.import.is_some()){
+            return Err("--export can't be used together with --machine or --import".to_string());
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use bevy::{input::InputSystem, prelude::*};
+
+///how far the left stick has to be pushed to press a direction
+const STICK_PRESS_THRESHOLD: f32 = 0.6;
+///how far the left stick has to come back to release it
+const STICK_RELEASE_THRESHOLD: f32 = 0.4;
+
+pub struct GamepadNavigationPlugin;
+
+impl Plugin for GamepadNavigationPlugin{
+    fn build(&self, app: &mut App){
+        app
+        .add_systems(
+            PreUpdate,
+            stick_to_dpad.after(InputSystem)
+        );
+    }
+}
+
+///whether `button` was just pressed on any gamepad
+pub fn just_pressed(gamepads: &Query<&Gamepad>, button: GamepadButton) -> bool{
+    gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+}
+
+///whether `button` is held on any gamepad
+pub fn pressed(gamepads: &Query<&Gamepad>, button: GamepadButton) -> bool{
+    gamepads.iter().any(|gamepad| gamepad.pressed(button))
+}
+
+///whether `button` was just released on any gamepad
+pub fn just_released(gamepads: &Query<&Gamepad>, button: GamepadButton) -> bool{
+    gamepads.iter().any(|gamepad| gamepad.just_released(button))
+}
+
+///presses the d-pad button the left stick points at, so everything listening to the d-pad works with the stick too
+fn stick_to_dpad(
+    mut gamepads: Query<(Entity, &mut Gamepad)>,
+    mut held: Local<HashMap<Entity, GamepadButton>>,
+){
+    for (entity, mut gamepad) in &mut gamepads{
+        let stick = gamepad.left_stick();
+        let current = held.get(&entity).copied();
+        let direction = current
+            .filter(|&button| along(stick, button) > STICK_RELEASE_THRESHOLD)
+            .or_else(|| pointed_at(stick));
+        if direction == current{
+            continue;
+        }
+
+        if let Some(button) = current{
+            gamepad.digital_mut().release(button);
+            held.remove(&entity);
+        }
+        if let Some(button) = direction{
+            gamepad.digital_mut().press(button);
+            held.insert(entity, button);
+        }
+    }
+}
+
+///how far the stick is pushed towards a d-pad button
+fn along(stick: Vec2, button: GamepadButton) -> f32{
+    match button{
+        GamepadButton::DPadUp => stick.y,
+        GamepadButton::DPadDown => -stick.y,
+        GamepadButton::DPadLeft => -stick.x,
+        GamepadButton::DPadRight => stick.x,
+        _ => 0.0,
+    }
+}
+
+///the d-pad button the stick is pushed furthest towards, if it is pushed far enough
+fn pointed_at(stick: Vec2) -> Option<GamepadButton>{
+    if stick.abs().max_element() < STICK_PRESS_THRESHOLD{
+        return None;
+    }
+    Some(match (stick.x.abs() > stick.y.abs(), stick.x > 0.0, stick.y > 0.0){
+        (true, true, _) => GamepadButton::DPadRight,
+        (true, false, _) => GamepadButton::DPadLeft,
+        (false, _, true) => GamepadButton::DPadUp,
+        (false, _, false) => GamepadButton::DPadDown,
+    })
+}
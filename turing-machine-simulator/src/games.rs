@@ -1,10 +1,16 @@
-use std::{collections::HashMap, fs, path::Path, slice::Iter};
-use bevy::{audio::PlaybackMode, input::{keyboard::{Key, KeyboardInput}, ButtonState}, prelude::*, render::mesh::Triangle2dMeshBuilder, text::FontSmoothing};
-use crate::{menus::MenuState, AppState, BaseFontSize, CurVolume, AUDIO_FILE_PREFIX};
-
-//Tape Cells
-const CELL_COUNT: usize = 1_000_000;
-const DEFAULT_CELL_CHAR: char = '_';
+use std::{any::TypeId, collections::HashMap, path::PathBuf, slice::Iter};
+use bevy::{audio::PlaybackMode, ecs::system::SystemParam, input::{keyboard::{Key, KeyboardInput}, ButtonState}, prelude::*, render::mesh::Triangle2dMeshBuilder, text::FontSmoothing, window::PrimaryWindow};
+use turing_core::{save::{self, Metadata}, Move, Simulation, Track};
+use crate::{actions::{Action, Actions}, assets::{AssetRegistry, GAME_SOUND_FILES}, menus::MenuState, paths::AppPaths, settings::{Bus, Settings}, toast::{show_toast, Toast}, AppState, BaseFontSize};
+use save_error::SaveErrorDialog;
+use symbol_picker::SymbolPicker;
+use branch_picker::BranchPicker;
+use tree_view::TreeView;
+use reload::{ReloadPrompt, WatchedMachine};
+use diagram_view::DiagramView;
+use edge_editor::EdgeEditor;
+use transition_table::TransitionTable;
+use run::{RunSpeed, RunState};
 
 //Visual Cells
 const CELL_SPACING_PER: f32 = 5.0;
@@ -13,9 +19,23 @@ const CELL_WIDTH: f32 = (100.0 - (CELL_SPACING_PER * (VISIBLE_CELL_COUNT + 1) as
 const BORDER_WIDTH_PER: f32 = 3.0;
 const MAIN_CELL_BORDER_WIDTH_PER: f32 = 5.0;
 const TEXT_FONT_SIZE: f32 = 80.0;
-
-const SAVE_FILE_PATH: &'static str = "assets/saves/world";
-const AUDIO_FILES: [&'static str; 5] = ["game-move.mp3", "game-cant-move.mp3", "game-select.mp3", "game-write.mp3", "game-delete.mp3"];
+//Tape Rows
+///gap between stacked tapes in vw, before scaling
+const ROW_SPACING_VW: f32 = 2.0;
+///tapes shown at full size, more than this and every row shrinks to fit
+const FULL_SIZE_ROW_COUNT: f32 = 2.0;
+const ACTIVE_TAPE_BORDER_COLOR: Color = Color::WHITE;
+//Cursor
+const CURSOR_HEIGHT: f32 = 100.0;
+///gap between the bottom tape and the tip of the cursor
+const CURSOR_GAP_PER: f32 = 11.5;
+//Overlay Panels
+const PANEL_COLOR: Color = Color::linear_rgba(0.0, 0.0, 0.0, 0.85);
+const BOTTOM_PANEL_WIDTH_PER: f32 = 90.0;
+const BOTTOM_PANEL_GAP_PER: f32 = 4.0;
+const BOTTOM_PANEL_TITLE_FONT_SIZE: f32 = 26.0;
+const CENTERED_PANEL_TITLE_FONT_SIZE: f32 = 40.0;
+const PANEL_HINT_FONT_SIZE: f32 = 20.0;
 
 ///types of in-game sound effects.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -29,7 +49,7 @@ enum GameSoundType{
 
 impl GameSoundType{
     pub fn iterator() -> Iter<'static, GameSoundType>{
-        static GAME_SOUND_TYPES: [GameSoundType; AUDIO_FILES.len()] = [GameSoundType::Move, GameSoundType::CantMove, GameSoundType::Select, GameSoundType::Write, GameSoundType::Delete];
+        static GAME_SOUND_TYPES: [GameSoundType; GAME_SOUND_FILES.len()] = [GameSoundType::Move, GameSoundType::CantMove, GameSoundType::Select, GameSoundType::Write, GameSoundType::Delete];
         GAME_SOUND_TYPES.iter()
     }
 }
@@ -40,29 +60,50 @@ struct GameSounds(HashMap<GameSoundType, Handle<AudioSource>>);
 #[derive(Component)]
 struct GameUI;
 
+///offset of a visible cell from the cursor
 #[derive(Component, Clone, Copy, Deref, DerefMut)]
 struct Cell(i32);
 
+///which tape a visible cell shows
+#[derive(Component, Clone, Copy, Deref)]
+struct TapeRow(usize);
+
+///the triangle under the middle cells
+#[derive(Component)]
+struct TapeCursor;
+
+///name of the save being played
 #[derive(Resource, Deref, DerefMut, Default)]
-pub struct SaveFileIndex(Option<usize>);
+pub struct SaveSlot(pub Option<String>);
 
-#[derive(Resource, Deref, DerefMut)]
-struct Tape{
-    cells: Box<Vec<char>>
+///machine description being played instead of a save, from `--machine`
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct MachineFile(pub Option<PathBuf>);
+
+///the machine and tapes being played with
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Sim{
+    #[deref]
+    simulation: Simulation,
+    metadata: Metadata,
+    ///tape the player moves along and writes to
+    active_tape: usize,
 }
 
-impl Default for Tape{
-    fn default() -> Self {
-        let mut cells = Box::new(Vec::new());
-        cells.resize(CELL_COUNT, DEFAULT_CELL_CHAR);
-        Self{
-            cells
-        }
+impl Sim{
+    fn active_tape(&self) -> usize{
+        self.active_tape.min(self.tracks.len() - 1)
     }
-}
 
-#[derive(Resource, Deref, DerefMut, Default)]
-struct CursorIndex(usize);
+    fn track(&self) -> &Track{
+        &self.tracks[self.active_tape()]
+    }
+
+    fn track_mut(&mut self) -> &mut Track{
+        let active_tape = self.active_tape();
+        &mut self.tracks[active_tape]
+    }
+}
 
 /// controls the current gamemode
 #[derive(States, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -72,6 +113,17 @@ pub enum GameState{
 }
 
 mod sandbox;
+mod run;
+mod save_error;
+mod symbol_picker;
+mod branch_picker;
+mod tree_view;
+mod reload;
+mod export;
+mod diagram_view;
+mod edge_editor;
+mod transition_table;
+mod hud;
 
 pub struct GamePlugin;
 
@@ -79,23 +131,63 @@ impl Plugin for GamePlugin{
     fn build(&self, app: &mut App){
         app
         .insert_state(GameState::None)
-        .insert_resource(Tape::default())
-        .insert_resource(CursorIndex::default())
-        .insert_resource(SaveFileIndex::default())
+        .add_sub_state::<RunState>()
+        .insert_resource(RunSpeed::default())
+        .insert_resource(Sim::default())
+        .insert_resource(SaveSlot::default())
+        .init_resource::<MachineFile>()
         .add_systems(
             Startup,
-            load_sounds,
+            (
+                load_sounds,
+                open_from_command_line.run_if(|file: Res<MachineFile>, slot: Res<SaveSlot>| file.is_some() || slot.is_some()),
+            ),
         )
         .add_systems(
         OnEnter(AppState::InGame),
-        load_ui
+        (load_ui, hud::spawn_hud)
         )
         .add_systems(
             Update,
             (
-                controls.run_if(in_state(AppState::InGame)),
-                write_to_cell.run_if(in_state(AppState::InGame)),
-                update_cells.run_if(in_state(AppState::InGame)),
+                controls.run_if(in_state(AppState::InGame)).run_if(not(overlay_open)),
+                run::run_controls.run_if(in_state(AppState::InGame)).run_if(not(overlay_open)),
+                run::run_machine.run_if(in_state(RunState::Running)),
+                write_to_cell.run_if(in_state(AppState::InGame)).run_if(not(overlay_open)),
+                export::export_diagram.run_if(in_state(AppState::InGame)).run_if(not(overlay_open)),
+                (
+                    transition_table::table_controls.run_if(in_state(AppState::InGame)).run_if(not(overlay_open_except::<TransitionTable>)),
+                    transition_table::update_table.run_if(resource_exists::<TransitionTable>),
+                ).chain(),
+                (
+                    diagram_view::view_controls.run_if(in_state(AppState::InGame)).run_if(not(overlay_open)),
+                    diagram_view::mouse_controls.run_if(resource_exists::<DiagramView>).run_if(not(overlay_open)),
+                    edge_editor::spawn_editor.run_if(resource_added::<EdgeEditor>),
+                    edge_editor::editor_controls.run_if(resource_exists::<EdgeEditor>),
+                    edge_editor::update_editor.run_if(resource_changed::<EdgeEditor>),
+                ).chain(),
+                (
+                    reload::watch_machine.run_if(resource_exists::<WatchedMachine>),
+                    reload::spawn_prompt.run_if(resource_added::<ReloadPrompt>),
+                    reload::prompt_controls.run_if(resource_exists::<ReloadPrompt>),
+                ).chain(),
+                symbol_picker::picker_controls.run_if(in_state(AppState::InGame)).run_if(not(overlay_open_except::<SymbolPicker>)),
+                symbol_picker::update_picker.run_if(resource_exists::<SymbolPicker>),
+                branch_picker::spawn_picker.run_if(resource_added::<BranchPicker>),
+                branch_picker::picker_controls.run_if(resource_exists::<BranchPicker>),
+                branch_picker::update_picker.run_if(resource_exists::<BranchPicker>),
+                tree_view::view_controls.run_if(in_state(AppState::InGame)).run_if(not(overlay_open_except::<TreeView>)),
+                tree_view::store_limit.run_if(resource_exists::<TreeView>),
+                (
+                    spawn_tape_rows.run_if(in_state(AppState::InGame)),
+                    update_cells.run_if(in_state(AppState::InGame)),
+                    place_cursor.run_if(in_state(AppState::InGame)),
+                    diagram_view::draw_diagram.run_if(resource_exists::<DiagramView>),
+                    hud::update_hud.run_if(in_state(AppState::InGame)),
+                ).chain(),
+                save_error::spawn_dialog.run_if(resource_added::<SaveErrorDialog>),
+                save_error::update_dialog.run_if(resource_exists::<SaveErrorDialog>),
+                save_error::dialog_controls.run_if(resource_exists::<SaveErrorDialog>),
         ).chain())
         .add_systems(
             OnExit(AppState::InGame),
@@ -105,124 +197,221 @@ impl Plugin for GamePlugin{
     }
 }
 
+///goes straight into the sandbox with the machine or imported save given on the command line
+fn open_from_command_line(
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+){
+    next_menu_state.set(MenuState::None);
+    next_game_state.set(GameState::Sandbox);
+    next_app_state.set(AppState::Transition);
+}
+
+///the dialogs, pickers and views that take the player's input while they are open
+#[derive(SystemParam)]
+struct Overlays<'w>{
+    dialog: Option<Res<'w, SaveErrorDialog>>,
+    symbol_picker: Option<Res<'w, SymbolPicker>>,
+    branch_picker: Option<Res<'w, BranchPicker>>,
+    tree_view: Option<Res<'w, TreeView>>,
+    reload_prompt: Option<Res<'w, ReloadPrompt>>,
+    edge_editor: Option<Res<'w, EdgeEditor>>,
+    transition_table: Option<Res<'w, TransitionTable>>,
+}
+
+impl Overlays<'_>{
+    ///the resource type of every overlay that is open
+    fn open(&self) -> impl Iterator<Item = TypeId>{
+        [
+            self.dialog.as_ref().map(|_| TypeId::of::<SaveErrorDialog>()),
+            self.symbol_picker.as_ref().map(|_| TypeId::of::<SymbolPicker>()),
+            self.branch_picker.as_ref().map(|_| TypeId::of::<BranchPicker>()),
+            self.tree_view.as_ref().map(|_| TypeId::of::<TreeView>()),
+            self.reload_prompt.as_ref().map(|_| TypeId::of::<ReloadPrompt>()),
+            self.edge_editor.as_ref().map(|_| TypeId::of::<EdgeEditor>()),
+            self.transition_table.as_ref().map(|_| TypeId::of::<TransitionTable>()),
+        ].into_iter().flatten()
+    }
+}
+
+///whether a dialog or picker is taking the player's input
+fn overlay_open(overlays: Overlays) -> bool{
+    overlays.open().next().is_some()
+}
+
+///whether a dialog or picker other than `T` is taking the player's input, for the controls that open and run `T`
+fn overlay_open_except<T: Resource>(overlays: Overlays) -> bool{
+    overlays.open().any(|open| open != TypeId::of::<T>())
+}
+
+///where an overlay's panel sits
+#[derive(Clone, Copy)]
+enum Placement{
+    ///along the bottom of the window, under the tapes
+    Bottom,
+    ///in the middle of the window, taking up `width` and `height` percent of it
+    Centered{width: f32, height: f32},
+}
+
+///a line of centered white text in an overlay's panel
+fn panel_text(text: impl Into<String>, font_size: f32) -> impl Bundle{
+    (
+        Text::new(text),
+        TextFont{
+            font_size,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(font_size),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+    )
+}
+
+///spawns an overlay's panel, with `title` at the top, whatever `body` spawns under it and `hint` at the bottom
+fn spawn_panel(
+    commands: &mut Commands,
+    marker: impl Component,
+    placement: Placement,
+    title: Option<&str>,
+    body: impl FnOnce(&mut ChildSpawnerCommands),
+    hint: Option<&str>,
+){
+    let (node, title_font_size) = match placement{
+        Placement::Bottom => (Node{
+            width: Val::Percent(BOTTOM_PANEL_WIDTH_PER),
+            bottom: Val::Percent(BOTTOM_PANEL_GAP_PER),
+            left: Val::Percent((100.0 - BOTTOM_PANEL_WIDTH_PER) / 2.0),
+            padding: UiRect::all(Val::Percent(1.0)),
+            ..Default::default()
+        }, BOTTOM_PANEL_TITLE_FONT_SIZE),
+        Placement::Centered{width, height} => (Node{
+            width: Val::Percent(width),
+            height: Val::Percent(height),
+            top: Val::Percent((100.0 - height) / 2.0),
+            left: Val::Percent((100.0 - width) / 2.0),
+            justify_content: JustifyContent::SpaceEvenly,
+            padding: UiRect::all(Val::Percent(2.0)),
+            overflow: Overflow::clip(),
+            ..Default::default()
+        }, CENTERED_PANEL_TITLE_FONT_SIZE),
+    };
+    commands.spawn((
+        GameUI,
+        marker,
+        Node{
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Percent(1.0),
+            ..node
+        },
+        BackgroundColor(PANEL_COLOR),
+        BorderRadius::all(Val::VMax(1.0)),
+        GlobalZIndex(1),
+    )).with_children(|panel| {
+        if let Some(title) = title{
+            panel.spawn(panel_text(title, title_font_size));
+        }
+        body(panel);
+        if let Some(hint) = hint{
+            panel.spawn(panel_text(hint, PANEL_HINT_FONT_SIZE));
+        }
+    });
+}
+
 fn load_sounds(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    registry: Res<AssetRegistry>,
 ){
     let mut sounds = HashMap::new();
-    for (&sound_type, &file_name) in GameSoundType::iterator().zip(AUDIO_FILES.iter()){
-        let path = AUDIO_FILE_PREFIX.to_owned() + file_name;
-        sounds.insert(sound_type, asset_server.load(path));
+    for (&sound_type, &file_name) in GameSoundType::iterator().zip(GAME_SOUND_FILES.iter()){
+        sounds.insert(sound_type, registry.sound(file_name));
     }
     commands.insert_resource(GameSounds(sounds));
 }
 
 /// loads the game elements
+#[allow(clippy::too_many_arguments)]
 fn load_ui(
     mut commands: Commands,
-    save_file_index: Res<SaveFileIndex>,
-    tape: ResMut<Tape>,
+    save_slot: ResMut<SaveSlot>,
+    machine_file: Res<MachineFile>,
+    sim: ResMut<Sim>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+    asset_server: Res<AssetServer>,
     game_state: Res<State<GameState>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut mats: ResMut<Assets<ColorMaterial>>,
-    mut cursor_index: ResMut<CursorIndex>,
 ){
-    //reset cursor
-    **cursor_index = 0;
-
-    //loads the cells
-    for i in 0..VISIBLE_CELL_COUNT{
-        commands.spawn((
-            GameUI,
-            Cell((i - VISIBLE_CELL_COUNT / 2) as i32),
-            Node{
-                position_type: PositionType::Absolute,
-                top: Val::Percent(CELL_SPACING_PER),
-                left: Val::Percent(CELL_SPACING_PER * (i + 1) as f32 + CELL_WIDTH * i as f32),
-                height: Val::Vw(CELL_WIDTH),
-                width: Val::Vw(CELL_WIDTH),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            Visibility::Visible,
-            BackgroundColor(Color::NONE),
-            Outline::new(if i == VISIBLE_CELL_COUNT / 2 {Val::Percent(MAIN_CELL_BORDER_WIDTH_PER)} else {Val::Percent(BORDER_WIDTH_PER)},
-             Val::Px(0.0), Color::BLACK),
-        )).with_child((
-            Text::new("_"),
-            TextFont{
-                font_size: TEXT_FONT_SIZE,
-                font_smoothing: FontSmoothing::AntiAliased,
-                ..Default::default()
-            },
-            BaseFontSize(TEXT_FONT_SIZE),
-            TextColor(Color::WHITE),
-            TextLayout::new_with_justify(JustifyText::Center),
-        ));
-    }
-
-    //loads cursor
+    //loads cursor, the cells are loaded once the world is
     commands.spawn((
         GameUI,
+        TapeCursor,
         Mesh2d(meshes.add(
-            Triangle2dMeshBuilder::new(Vec2::new(0.0, 100.0),
+            Triangle2dMeshBuilder::new(Vec2::new(0.0, CURSOR_HEIGHT),
             Vec2::new(-50.0, 0.0),
             Vec2::new(50.0, 0.0),
         ))),
         MeshMaterial2d(mats.add(Color::BLACK)),
-        //Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
     ));
     
     match **game_state{
-        GameState::Sandbox => sandbox::load(commands, save_file_index, tape),
+        GameState::Sandbox => sandbox::load(commands, save_slot, machine_file, sim, toasts, paths, asset_server),
         _ => println!("unimplemented menu"),
     }
 }
 
 ///handles user inputs
+#[allow(clippy::too_many_arguments)]
 fn controls(
-    mut cursor: ResMut<CursorIndex>,
-    inputs: Res<ButtonInput<KeyCode>>,
-    mut cells: Query<&mut Cell>,
-    mut tape: ResMut<Tape>,
+    mut sim: ResMut<Sim>,
+    actions: Actions,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut next_app_state: ResMut<NextState<AppState>>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
     mut commands: Commands,
     sounds: Res<GameSounds>,
-    volume: Res<CurVolume>,
+    settings: Res<Settings>,
 ){
-    let volume = volume.0;
-    let initial_cursor = **cursor;
+    let volume = settings.volume(Bus::TapeEffects);
+    let mut cursor_moved = false;
     let mut cursor_tried_move = false; 
-    if inputs.just_pressed(KeyCode::ArrowLeft){
-        **cursor = cursor.checked_sub(1).unwrap_or(0);
+    if actions.just_pressed(Action::MoveLeft){
+        cursor_moved |= sim.track_mut().move_head(Move::Left);
         cursor_tried_move = true;
     }
-    if inputs.just_pressed(KeyCode::ArrowRight){
-        **cursor += 1;
+    if actions.just_pressed(Action::MoveRight){
+        cursor_moved |= sim.track_mut().move_head(Move::Right);
+        cursor_tried_move = true;
+    }
+    if actions.just_pressed(Action::PreviousTape){
+        let active_tape = sim.active_tape();
+        cursor_moved |= active_tape > 0;
+        sim.active_tape = active_tape.saturating_sub(1);
+        cursor_tried_move = true;
+    }
+    if actions.just_pressed(Action::NextTape){
+        let active_tape = sim.active_tape();
+        cursor_moved |= active_tape + 1 < sim.tracks.len();
+        sim.active_tape = (active_tape + 1).min(sim.tracks.len() - 1);
         cursor_tried_move = true;
     }
 
-    **cursor = cursor.clamp(0, CELL_COUNT - 1);
-
-    let cursor_moved = initial_cursor != **cursor;
     if cursor_moved{
-        for mut cell_index in &mut cells{
-            **cell_index += if initial_cursor < **cursor {1} else {-1};
-        }
-        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Move].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
+        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Move].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
     }else if cursor_tried_move{
-        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::CantMove].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
+        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::CantMove].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
     }
     
-    if inputs.just_pressed(KeyCode::Backspace){
-        tape[**cursor] = DEFAULT_CELL_CHAR;
-        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Delete].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
+    if actions.just_pressed(Action::Erase){
+        sim.track_mut().erase();
+        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Delete].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
     }
 
-    if inputs.just_pressed(KeyCode::Escape){
+    if actions.just_pressed(Action::Exit){
         next_game_state.set(GameState::None);
         next_app_state.set(AppState::Transition);
         next_menu_state.set(MenuState::GameMenu);
@@ -230,20 +419,20 @@ fn controls(
 }
 
 fn write_to_cell(
-    cursor: Res<CursorIndex>,
-    mut tape: ResMut<Tape>,
+    mut sim: ResMut<Sim>,
     mut keyboard: EventReader<KeyboardInput>,
     mut commands: Commands,
     sounds: Res<GameSounds>,
-    volume: Res<CurVolume>,
+    settings: Res<Settings>,
 ){
-    let volume = volume.0;
+    let volume = settings.volume(Bus::TapeEffects);
     if keyboard.is_empty(){
         return;
     }
     let mut char_to_write = None;
     for e in keyboard.read(){
-        if e.state == ButtonState::Released{
+        //keys bound to actions don't write
+        if e.state == ButtonState::Released || settings.bindings.is_bound(e.key_code){
             continue;
         }
 
@@ -255,64 +444,151 @@ fn write_to_cell(
     }
 
     if let Some(c) = char_to_write{
-        tape[**cursor] = c;
-        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Write].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
+        //only symbols of the machine's tape alphabet can be written
+        let sound = if sim.machine.alphabet.is_tape(c){
+            sim.track_mut().write(c);
+            GameSoundType::Write
+        }else{
+            GameSoundType::CantMove
+        };
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///spawns a row of cells for each tape, again whenever the number of tapes changes
+fn spawn_tape_rows(
+    mut commands: Commands,
+    sim: Res<Sim>,
+    cells: Query<(Entity, &TapeRow)>,
+){
+    let tape_count = sim.tracks.len();
+    let row_count = cells.iter().map(|(_, row)| **row + 1).max().unwrap_or(0);
+    if row_count == tape_count{
+        return;
+    }
+    for (entity, _) in &cells{
+        commands.entity(entity).despawn();
+    }
+
+    let scale = row_scale(tape_count);
+    for row in 0..tape_count{
+        for i in 0..VISIBLE_CELL_COUNT{
+            commands.spawn((
+                GameUI,
+                Cell((i - VISIBLE_CELL_COUNT / 2) as i32),
+                TapeRow(row),
+                Node{
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(CELL_SPACING_PER),
+                    left: Val::Percent(CELL_SPACING_PER * (i + 1) as f32 + CELL_WIDTH * i as f32),
+                    margin: UiRect::top(Val::Vw((CELL_WIDTH + ROW_SPACING_VW) * scale * row as f32)),
+                    height: Val::Vw(CELL_WIDTH * scale),
+                    width: Val::Vw(CELL_WIDTH),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                Visibility::Visible,
+                BackgroundColor(Color::NONE),
+                Outline::new(if i == VISIBLE_CELL_COUNT / 2 {Val::Percent(MAIN_CELL_BORDER_WIDTH_PER)} else {Val::Percent(BORDER_WIDTH_PER)},
+                 Val::Px(0.0), Color::BLACK),
+            )).with_child((
+                Text::new("_"),
+                TextFont{
+                    font_size: TEXT_FONT_SIZE * scale,
+                    font_smoothing: FontSmoothing::AntiAliased,
+                    ..Default::default()
+                },
+                BaseFontSize(TEXT_FONT_SIZE * scale),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+        }
     }
 }
 
+///how big each tape's cells are, so that many tapes still fit above the cursor
+fn row_scale(tape_count: usize) -> f32{
+    (FULL_SIZE_ROW_COUNT / tape_count as f32).min(1.0)
+}
+
 fn update_cells(
-    tape: Res<Tape>,
-    mut cells: Query<(&Cell, &mut Children, &mut Visibility)>,
+    sim: Res<Sim>,
+    mut cells: Query<(&Cell, &TapeRow, &Children, &mut Outline)>,
     mut children_query: Query<&mut Text>,
 ){
-    for (&cell_index, children, mut vis) in &mut cells{
-        match tape.get(*cell_index as usize){
-            None => {
-                *vis = Visibility::Hidden;
-            },
-            Some(&c) => {
-                *vis = Visibility::Visible;
-                let child = children.iter().next().unwrap();
-                if let Ok(mut text) = children_query.get_mut(child){
-                    text.0 = c.to_string();
-                }
-            }
+    let active_tape = sim.active_tape();
+    for (&cell_offset, &row, children, mut outline) in &mut cells{
+        let Some(track) = sim.tracks.get(*row) else{
+            continue;
+        };
+        let c = track.tape[track.head + *cell_offset as i64];
+        let child = children.iter().next().unwrap();
+        if let Ok(mut text) = children_query.get_mut(child){
+            text.0 = c.to_string();
         }
+        //only worth pointing out which tape is being edited when there's a choice
+        let active = *cell_offset == 0 && *row == active_tape && sim.tracks.len() > 1;
+        outline.color = if active {ACTIVE_TAPE_BORDER_COLOR} else {Color::BLACK};
+    }
+}
+
+///how far the bottom of the lowest tape is from the top of the window, in pixels
+fn tapes_bottom(window: &Window, tape_count: usize) -> f32{
+    let scale = row_scale(tape_count);
+    let rows = tape_count as f32;
+    let stack_height_vw = (CELL_WIDTH * rows + ROW_SPACING_VW * (rows - 1.0)) * scale;
+    window.height() * CELL_SPACING_PER / 100.0 + window.width() * stack_height_vw / 100.0
+}
+
+///keeps the cursor just under the bottom tape
+fn place_cursor(
+    sim: Res<Sim>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cursors: Query<&mut Transform, With<TapeCursor>>,
+){
+    let Ok(window) = windows.single() else{
+        return;
+    };
+    let tip_from_top = tapes_bottom(window, sim.tracks.len()) + window.height() * CURSOR_GAP_PER / 100.0;
+    for mut transform in &mut cursors{
+        transform.translation.y = window.height() / 2.0 - tip_from_top - CURSOR_HEIGHT;
     }
 }
 
 ///unloads all game elements
 fn unload_ui(
     mut commands: Commands,
-    mut save_file_index: ResMut<SaveFileIndex>,
+    mut save_slot: ResMut<SaveSlot>,
+    mut machine_file: ResMut<MachineFile>,
     mut ui_elements: Query<Entity, With<GameUI>>,
-    mut tape: ResMut<Tape>,
+    mut sim: ResMut<Sim>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
 ){
     for entity in &mut ui_elements{
         commands.get_entity(entity).unwrap().despawn();
     }
 
-    match **save_file_index{
+    match save_slot.take(){
         None => (),
-        Some(i) =>{
-            let mut contents = Box::new(String::new());
-            let mut empty_count = 0;
-            for &cell in tape.iter(){
-                if cell == '_'{
-                    empty_count += 1;
-                }else{
-                    contents.push_str(&"_".repeat(empty_count));
-                    empty_count = 0;
-                    contents.push(cell);
-                }
+        Some(name) =>{
+            let sim = &mut *sim;
+            if let Err(e) = save::write_world(&sandbox::save_path(&paths, &name), &sim.simulation, &mut sim.metadata){
+                show_toast(&mut commands, &toasts, format!("Couldn't save {}: {}", name, e));
             }
-
-            let _ = fs::write(Path::new(&format!("{}{}.sav", SAVE_FILE_PATH, i)), *contents);
-            **save_file_index = None;
         } 
     }
 
-    for cell in tape.iter_mut(){
-        *cell = DEFAULT_CELL_CHAR;
-    }
-}
\ No newline at end of file
+    //the machine file is only opened once, saves are picked from the menu after that
+    **machine_file = None;
+    *sim = Sim::default();
+    commands.remove_resource::<SymbolPicker>();
+    commands.remove_resource::<BranchPicker>();
+    commands.remove_resource::<TreeView>();
+    commands.remove_resource::<WatchedMachine>();
+    commands.remove_resource::<ReloadPrompt>();
+    commands.remove_resource::<DiagramView>();
+    commands.remove_resource::<EdgeEditor>();
+    commands.remove_resource::<TransitionTable>();
+}
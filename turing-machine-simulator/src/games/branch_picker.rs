@@ -0,0 +1,139 @@
+use bevy::{audio::PlaybackMode, prelude::*, text::FontSmoothing};
+use turing_core::{Move, Transition};
+
+use crate::{actions::{Action, Actions}, gamepad, games::{spawn_panel, GameSoundType, GameSounds, Placement, Sim}, settings::{Bus, Settings}, BaseFontSize};
+
+//title
+const TITLE_TEXT: &str = "Nondeterministic step, choose a branch";
+//choices
+const CHOICE_FONT_SIZE: f32 = 26.0;
+const CHOICE_UNSELECTED_COLOR: Color = Color::linear_rgb(0.25, 0.25, 0.25);
+const CHOICE_SELECTED_COLOR: Color = Color::WHITE;
+const CHOICE_TEXT_COLOR: Color = Color::BLACK;
+//hint
+const HINT_TEXT: &str = "Left/Right: choose   Step: follow   Exit: cancel";
+
+///choice of transition for a nondeterministic step, open while this exists
+#[derive(Resource, Default)]
+pub struct BranchPicker{
+    selected: usize,
+    ///false on the frame the picker opened, so the step that opened it doesn't also follow a branch
+    armed: bool,
+}
+
+///marker for the picker's ui
+#[derive(Component)]
+pub struct BranchUI;
+
+#[derive(Component, Deref)]
+pub struct BranchChoice(usize);
+
+///what a choice does, such as `q1, write 1, move R`
+fn describe(transition: &Transition) -> String{
+    let write: String = transition.write.iter().collect();
+    let movement: String = transition.movement.iter()
+        .map(|movement| match movement{
+            Move::Left => 'L',
+            Move::Right => 'R',
+            Move::Stay => 'S',
+        })
+        .collect();
+    format!("{}, write {}, move {}", transition.next_state, write, movement)
+}
+
+///chooses a branch with left and right, then follows it
+#[allow(clippy::too_many_arguments)]
+pub fn picker_controls(
+    mut commands: Commands,
+    mut picker: ResMut<BranchPicker>,
+    actions: Actions,
+    gamepads: Query<&Gamepad>,
+    mut sim: ResMut<Sim>,
+    picker_ui: Query<Entity, With<BranchUI>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    if !picker.armed{
+        picker.armed = true;
+        return;
+    }
+    let volume = settings.volume(Bus::TapeEffects);
+    let mut sound = None;
+    let count = sim.choices().len();
+    if actions.just_pressed(Action::MoveLeft){
+        picker.selected = picker.selected.checked_sub(1).unwrap_or(count.saturating_sub(1));
+        sound = Some(GameSoundType::Move);
+    }
+    if actions.just_pressed(Action::MoveRight){
+        picker.selected = (picker.selected + 1) % count.max(1);
+        sound = Some(GameSoundType::Move);
+    }
+
+    let follow = actions.just_pressed(Action::Step) || gamepad::just_pressed(&gamepads, GamepadButton::South);
+    if follow{
+        let initial_steps = sim.steps;
+        let selected = picker.selected;
+        sim.step_choice(selected);
+        sound = Some(if sim.steps != initial_steps {GameSoundType::Select} else {GameSoundType::CantMove});
+    }
+    if follow || actions.just_pressed(Action::Exit) || gamepad::just_pressed(&gamepads, GamepadButton::East){
+        commands.remove_resource::<BranchPicker>();
+        for entity in &picker_ui{
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///shows the choices for the step the machine is about to take
+pub fn spawn_picker(
+    mut commands: Commands,
+    sim: Res<Sim>,
+){
+    spawn_panel(&mut commands, BranchUI, Placement::Bottom, Some(TITLE_TEXT), |panel| {
+        panel.spawn(Node{
+            flex_wrap: FlexWrap::Wrap,
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Percent(1.0),
+            row_gap: Val::Percent(1.0),
+            ..Default::default()
+        }).with_children(|row| {
+            for (i, transition) in sim.choices().iter().enumerate(){
+                row.spawn((
+                    BranchChoice(i),
+                    Node{
+                        padding: UiRect::axes(Val::Vw(1.5), Val::Vw(0.5)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    BackgroundColor(CHOICE_UNSELECTED_COLOR),
+                    BorderRadius::all(Val::VMax(1.0)),
+                )).with_child((
+                    Text::new(describe(transition)),
+                    TextFont{
+                        font_size: CHOICE_FONT_SIZE,
+                        font_smoothing: FontSmoothing::AntiAliased,
+                        ..Default::default()
+                    },
+                    BaseFontSize(CHOICE_FONT_SIZE),
+                    TextColor(CHOICE_TEXT_COLOR),
+                    TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+                ));
+            }
+        });
+    }, Some(HINT_TEXT));
+}
+
+///highlights the selected choice
+pub fn update_picker(
+    picker: Res<BranchPicker>,
+    mut choices: Query<(&BranchChoice, &mut BackgroundColor)>,
+){
+    for (index, mut bgc) in &mut choices{
+        bgc.0 = if **index == picker.selected {CHOICE_SELECTED_COLOR} else {CHOICE_UNSELECTED_COLOR};
+    }
+}
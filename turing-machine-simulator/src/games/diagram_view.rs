@@ -0,0 +1,322 @@
+use std::collections::HashSet;
+use bevy::{audio::PlaybackMode, prelude::*, sprite::Anchor, text::FontSmoothing, window::PrimaryWindow};
+use turing_core::{diagram::{self, Curve, Layout, ACCEPT_RING_GAP, FONT_SIZE, START_ARROW_LENGTH, STATE_RADIUS}, import, Point};
+
+use crate::{actions::{Action, Actions}, games::{edge_editor::EdgeEditor, reload::{self, WatchedMachine}, run::RunState, GameSoundType, GameSounds, GameUI, Sim, TapeCursor}, settings::{Bus, Settings}, toast::Toast};
+
+//area under the tape cursor the diagram is fitted into, in pixels
+const AREA_GAP: f32 = 20.0;
+const AREA_MARGIN: f32 = 30.0;
+///largest the diagram is drawn, small machines don't fill the area
+const MAX_SCALE: f32 = 1.5;
+//drawing
+const STATE_COLOR: Color = Color::WHITE;
+const ACCEPT_COLOR: Color = Color::linear_rgb(0.2, 0.8, 0.3);
+const REJECT_COLOR: Color = Color::linear_rgb(0.9, 0.15, 0.15);
+///the state the machine is in and the edge it just took
+const HIGHLIGHT_COLOR: Color = Color::linear_rgb(1.0, 0.75, 0.0);
+const LINK_COLOR: Color = Color::linear_rgb(0.5, 0.5, 0.5);
+const LABEL_COLOR: Color = Color::WHITE;
+///gap between the current state's circle and the ring around it
+const HIGHLIGHT_RING_GAP: f32 = 4.0;
+const ARROW_TIP_LENGTH: f32 = 10.0;
+///straight lines each edge is drawn with
+const CURVE_SEGMENTS: usize = 24;
+///how close to an edge a click has to be to pick it, in pixels
+const EDGE_PICK_DISTANCE: f32 = 8.0;
+///name new states are numbered after
+const NEW_STATE_NAME: &str = "q";
+
+///how diagram points map onto the screen
+#[derive(Clone, Copy, PartialEq)]
+struct Fit{
+    scale: f32,
+    ///where the diagram's origin is in the world
+    origin: Vec2,
+}
+
+impl Fit{
+    ///fits the whole layout into the area under the tape cursor
+    fn new(layout: &Layout, window: &Window, cursor_y: f32) -> Self{
+        let top = cursor_y - AREA_GAP;
+        let bottom = -window.height() / 2.0 + AREA_MARGIN;
+        let width = window.width() - AREA_MARGIN * 2.0;
+        let height = (top - bottom).max(1.0);
+        let (top_left, bottom_right) = layout.bounds;
+        let scale = (width / (bottom_right.x - top_left.x))
+            .min(height / (bottom_right.y - top_left.y))
+            .min(MAX_SCALE);
+        let center = Vec2::new((top_left.x + bottom_right.x) / 2.0, -(top_left.y + bottom_right.y) / 2.0);
+        Self{
+            scale,
+            origin: Vec2::new(0.0, (top + bottom) / 2.0) - center * scale,
+        }
+    }
+
+    fn world(&self, point: Point) -> Vec2{
+        self.origin + Vec2::new(point.x, -point.y) * self.scale
+    }
+
+    fn diagram(&self, world: Vec2) -> Point{
+        let point = (world - self.origin) / self.scale;
+        Point{x: point.x, y: -point.y}
+    }
+}
+
+///what the mouse is doing to the diagram
+enum Gesture{
+    ///moving a state, which is `offset` from the mouse
+    Drag{state: String, offset: Point},
+    ///drawing a new arrow out of a state
+    Link{from: String, to: Vec2},
+}
+
+///a label drawn over the diagram
+#[derive(Clone, PartialEq)]
+struct Label{
+    text: String,
+    at: Vec2,
+    anchor: Anchor,
+    justify: JustifyText,
+    font_size: f32,
+}
+
+///the state diagram under the tapes, shown while this exists
+#[derive(Resource, Default)]
+pub struct DiagramView{
+    ///kept while a state is being dragged so it doesn't move out from under the mouse
+    fit: Option<Fit>,
+    gesture: Option<Gesture>,
+    ///labels currently spawned
+    labels: Vec<Label>,
+}
+
+///marker for the diagram's labels
+#[derive(Component)]
+pub struct DiagramLabel;
+
+///opens and closes the diagram
+pub fn view_controls(
+    mut commands: Commands,
+    view: Option<Res<DiagramView>>,
+    actions: Actions,
+    labels: Query<Entity, With<DiagramLabel>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    if !actions.just_pressed(Action::StateDiagram){
+        return;
+    }
+    if view.is_some(){
+        commands.remove_resource::<DiagramView>();
+        for entity in &labels{
+            commands.entity(entity).despawn();
+        }
+    }else{
+        commands.insert_resource(DiagramView::default());
+    }
+    commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Select].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+}
+
+///a name for a new state that the machine doesn't use yet
+fn new_state_name(layout: &Layout) -> String{
+    let used: HashSet<String> = layout.states.keys().cloned().collect();
+    import::unused_state(&format!("{}{}", NEW_STATE_NAME, layout.states.len()), &used)
+}
+
+///drags states around, draws arrows out of them with shift held, opens edges to edit with a click and deletes them with a right click.
+///machines reloaded from a file can only have their states moved
+#[allow(clippy::too_many_arguments)]
+pub fn mouse_controls(
+    mut commands: Commands,
+    mut view: ResMut<DiagramView>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut sim: ResMut<Sim>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+    watched: Option<Res<WatchedMachine>>,
+    toasts: Query<Entity, With<Toast>>,
+){
+    let Some(fit) = view.fit else{
+        return;
+    };
+    let Some(cursor) = windows.single().ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|position| {
+            let (camera, transform) = cameras.single().ok()?;
+            camera.viewport_to_world_2d(transform, position).ok()
+        })
+    else{
+        return;
+    };
+    let point = fit.diagram(cursor);
+    let layout = diagram::layout(&sim.machine);
+    let mut sound = None;
+
+    if mouse.just_pressed(MouseButton::Left){
+        if let Some(state) = layout.state_at(point){
+            if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]){
+                if reload::refuse_edit(&mut commands, &toasts, watched.as_deref()){
+                    sound = Some(GameSoundType::CantMove);
+                }else{
+                    view.gesture = Some(Gesture::Link{from: state.to_string(), to: cursor});
+                }
+            }else{
+                //every state is pinned where it is, otherwise the ones placed automatically move around it
+                sim.machine.layout = layout.states.clone();
+                let center = layout.states[state];
+                view.gesture = Some(Gesture::Drag{state: state.to_string(), offset: Point{x: center.x - point.x, y: center.y - point.y}});
+            }
+        }else if let Some(edge) = layout.edge_at(point, EDGE_PICK_DISTANCE / fit.scale){
+            if reload::refuse_edit(&mut commands, &toasts, watched.as_deref()){
+                sound = Some(GameSoundType::CantMove);
+            }else{
+                next_run_state.set(RunState::Paused);
+                commands.insert_resource(EdgeEditor::open(&sim.machine, &edge.from, &edge.to, None));
+                sound = Some(GameSoundType::Select);
+            }
+        }
+    }
+
+    match &mut view.gesture{
+        //the state is gone if the machine was reloaded in the meantime
+        Some(Gesture::Drag{state, ..} | Gesture::Link{from: state, ..}) if !layout.states.contains_key(state) => view.gesture = None,
+        Some(Gesture::Drag{state, offset}) => {
+            let moved = Point{x: point.x + offset.x, y: point.y + offset.y};
+            sim.machine.layout.insert(state.clone(), moved);
+        },
+        Some(Gesture::Link{to, ..}) => *to = cursor,
+        None => (),
+    }
+
+    if mouse.just_released(MouseButton::Left) && let Some(Gesture::Link{from, ..}) = view.gesture.take(){
+        //letting go away from every state makes a new one there
+        let (to, place) = match layout.state_at(point){
+            Some(state) => (state.to_string(), None),
+            None => (new_state_name(&layout), Some(point)),
+        };
+        next_run_state.set(RunState::Paused);
+        commands.insert_resource(EdgeEditor::open(&sim.machine, &from, &to, place));
+        sound = Some(GameSoundType::Select);
+    }
+    if !mouse.pressed(MouseButton::Left){
+        view.gesture = None;
+    }
+
+    if mouse.just_pressed(MouseButton::Right)
+        && let Some(edge) = layout.edge_at(point, EDGE_PICK_DISTANCE / fit.scale){
+        if reload::refuse_edit(&mut commands, &toasts, watched.as_deref()){
+            sound = Some(GameSoundType::CantMove);
+        }else{
+            let mut machine = sim.machine.clone();
+            machine.remove_edge(&edge.from, &edge.to);
+            sim.replace_machine(machine);
+            sound = Some(GameSoundType::Delete);
+        }
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///the sprite anchor and justification that line an edge label up like the diagram says,
+///on its last line
+fn edge_anchor(anchor: diagram::Anchor) -> (Anchor, JustifyText){
+    match anchor{
+        diagram::Anchor::Start => (Anchor::BottomLeft, JustifyText::Left),
+        diagram::Anchor::Middle => (Anchor::BottomCenter, JustifyText::Center),
+        diagram::Anchor::End => (Anchor::BottomRight, JustifyText::Right),
+    }
+}
+
+///draws an edge's curve with an arrow head at its end
+fn draw_curve(gizmos: &mut Gizmos, fit: Fit, curve: &Curve, color: Color){
+    let points: Vec<Vec2> = (0..=CURVE_SEGMENTS).map(|i| fit.world(curve.point(i as f32 / CURVE_SEGMENTS as f32))).collect();
+    gizmos.linestrip_2d(points[..CURVE_SEGMENTS].iter().copied(), color);
+    gizmos.arrow_2d(points[CURVE_SEGMENTS - 1], points[CURVE_SEGMENTS], color).with_tip_length(ARROW_TIP_LENGTH * fit.scale);
+}
+
+///draws the diagram under the tapes, highlighting the current state and the edge just taken
+pub fn draw_diagram(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    mut view: ResMut<DiagramView>,
+    sim: Res<Sim>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cursors: Query<&Transform, With<TapeCursor>>,
+    labels: Query<Entity, With<DiagramLabel>>,
+){
+    let (Ok(window), Ok(cursor)) = (windows.single(), cursors.single()) else{
+        return;
+    };
+    let layout = diagram::layout(&sim.machine);
+    let fit = match (view.fit, &view.gesture){
+        (Some(fit), Some(Gesture::Drag{..})) => fit,
+        _ => Fit::new(&layout, window, cursor.translation.y),
+    };
+    view.fit = Some(fit);
+
+    let last_step = sim.last_step();
+    let mut wanted = Vec::new();
+    for edge in &layout.edges{
+        let taken = last_step == Some((edge.from.as_str(), edge.to.as_str()));
+        draw_curve(&mut gizmos, fit, &edge.curve, if taken {HIGHLIGHT_COLOR} else {STATE_COLOR});
+        let (anchor, justify) = edge_anchor(edge.anchor);
+        wanted.push(Label{text: edge.lines.join("\n"), at: fit.world(edge.label), anchor, justify, font_size: FONT_SIZE * fit.scale});
+    }
+    //the state being linked from is gone if the machine was reloaded or edited in the meantime
+    if let Some(Gesture::Link{from, to}) = &view.gesture{
+        match layout.states.get(from){
+            Some(&from) => {
+                gizmos.arrow_2d(fit.world(from), *to, LINK_COLOR).with_tip_length(ARROW_TIP_LENGTH * fit.scale);
+            },
+            None => view.gesture = None,
+        }
+    }
+
+    let start = fit.world(layout.states[&sim.machine.start_state]);
+    gizmos.arrow_2d(start - Vec2::X * (STATE_RADIUS + START_ARROW_LENGTH) * fit.scale, start - Vec2::X * STATE_RADIUS * fit.scale, STATE_COLOR)
+        .with_tip_length(ARROW_TIP_LENGTH * fit.scale);
+    for (state, &point) in &layout.states{
+        let center = fit.world(point);
+        let color = if *state == sim.machine.accept_state {ACCEPT_COLOR} else if *state == sim.machine.reject_state {REJECT_COLOR} else {STATE_COLOR};
+        gizmos.circle_2d(center, STATE_RADIUS * fit.scale, color);
+        if *state == sim.machine.accept_state{
+            gizmos.circle_2d(center, (STATE_RADIUS - ACCEPT_RING_GAP) * fit.scale, color);
+        }
+        if *state == sim.state{
+            gizmos.circle_2d(center, (STATE_RADIUS + HIGHLIGHT_RING_GAP) * fit.scale, HIGHLIGHT_COLOR);
+        }
+        wanted.push(Label{text: state.clone(), at: center, anchor: Anchor::Center, justify: JustifyText::Center, font_size: FONT_SIZE * fit.scale});
+    }
+
+    //labels are only respawned when something about them changes
+    if wanted == view.labels{
+        return;
+    }
+    for entity in &labels{
+        commands.entity(entity).despawn();
+    }
+    for label in &wanted{
+        commands.spawn((
+            GameUI,
+            DiagramLabel,
+            Text2d::new(label.text.clone()),
+            TextFont{
+                font_size: label.font_size,
+                font_smoothing: FontSmoothing::AntiAliased,
+                ..Default::default()
+            },
+            TextColor(LABEL_COLOR),
+            TextLayout::new_with_justify(label.justify),
+            label.anchor,
+            Transform::from_translation(label.at.extend(1.0)),
+        ));
+    }
+    view.labels = wanted;
+}
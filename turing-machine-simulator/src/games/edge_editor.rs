@@ -0,0 +1,225 @@
+use bevy::{audio::PlaybackMode, input::{keyboard::{Key, KeyboardInput}, ButtonState}, prelude::*, text::FontSmoothing};
+use turing_core::{Machine, Move, Point, Transition};
+
+use crate::{gamepad, games::{panel_text, spawn_panel, GameSoundType, GameSounds, Placement, Sim}, settings::{Bus, Settings}, BaseFontSize};
+
+//text
+const LINES_FONT_SIZE: f32 = 24.0;
+const ERROR_FONT_SIZE: f32 = 20.0;
+const ERROR_COLOR: Color = Color::linear_rgb(1.0, 0.3, 0.3);
+const HINT_TEXT: &str = "One transition per line: read write move, like 0 1 R\nUp/Down: line   Delete: clear line   Enter: save   Escape: cancel";
+///marks the line being typed on
+const LINE_MARKER: &str = "> ";
+const CARET: char = '_';
+
+///the transitions of one arrow of the diagram being edited as text, open while this exists
+#[derive(Resource)]
+pub struct EdgeEditor{
+    from: String,
+    to: String,
+    ///one transition each, as `read write move`
+    lines: Vec<String>,
+    ///line being typed on
+    line: usize,
+    error: Option<String>,
+    ///where to put `to` when it is a new state
+    place: Option<Point>,
+}
+
+impl EdgeEditor{
+    ///edits the transitions from `from` to `to`, with an empty line at the end for a new one
+    pub fn open(machine: &Machine, from: &str, to: &str, place: Option<Point>) -> Self{
+        let mut lines: Vec<String> = machine.edge(from, to).iter()
+            .map(|(read, transition)| format!("{} {} {}", read.iter().collect::<String>(), transition.write.iter().collect::<String>(), transition.movement.iter().map(|&movement| move_letter(movement)).collect::<String>()))
+            .collect();
+        lines.push(String::new());
+        Self{
+            from: from.to_string(),
+            to: to.to_string(),
+            line: lines.len() - 1,
+            lines,
+            error: None,
+            place,
+        }
+    }
+
+    ///the transitions the lines describe, or what is wrong with the first line that doesn't make sense
+    fn transitions(&self, machine: &Machine) -> Result<Vec<(Vec<char>, Transition)>, String>{
+        self.lines.iter().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| parse_line(line, &self.to, machine).map_err(|e| format!("line {}: {}", i + 1, e)))
+            .collect()
+    }
+}
+
+///marker for the editor's ui
+#[derive(Component)]
+pub struct EditorUI;
+
+///the text showing the lines
+#[derive(Component)]
+pub struct EditorLines;
+
+///the text showing what is wrong
+#[derive(Component)]
+pub struct EditorError;
+
+fn move_letter(movement: Move) -> char{
+    match movement{
+        Move::Left => 'L',
+        Move::Right => 'R',
+        Move::Stay => 'S',
+    }
+}
+
+///reads `read write move` into a transition going to `next_state`
+fn parse_line(line: &str, next_state: &str, machine: &Machine) -> Result<(Vec<char>, Transition), String>{
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let &[read, write, moves] = &words[..] else{
+        return Err(format!("expected 3 fields: read write move, found {}", words.len()));
+    };
+    let tape_count = machine.tape_count;
+    let read: Vec<char> = read.chars().collect();
+    let write: Vec<char> = write.chars().collect();
+    for (what, symbols) in [("read", &read), ("write", &write)]{
+        if symbols.len() != tape_count{
+            return Err(format!("expected {} {} symbols, one per tape, found {}", tape_count, what, symbols.len()));
+        }
+        if let Some(c) = symbols.iter().find(|&&c| !machine.alphabet.is_tape(c)){
+            return Err(format!("'{}' isn't in the tape alphabet", c));
+        }
+    }
+    let movement = moves.chars()
+        .map(|c| Move::parse(&c.to_string()).ok_or(format!("unknown move '{}', use L, R or S", c)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if movement.len() != tape_count{
+        return Err(format!("expected {} moves, one per tape, found {}", tape_count, movement.len()));
+    }
+    Ok((read, Transition{next_state: next_state.to_string(), write, movement}))
+}
+
+fn close(commands: &mut Commands, editor_ui: &Query<Entity, With<EditorUI>>){
+    commands.remove_resource::<EdgeEditor>();
+    for entity in editor_ui{
+        commands.entity(entity).despawn();
+    }
+}
+
+///types the transitions, then puts them in the machine in place of the old ones
+#[allow(clippy::too_many_arguments)]
+pub fn editor_controls(
+    mut commands: Commands,
+    mut editor: ResMut<EdgeEditor>,
+    mut keyboard: EventReader<KeyboardInput>,
+    gamepads: Query<&Gamepad>,
+    mut sim: ResMut<Sim>,
+    editor_ui: Query<Entity, With<EditorUI>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    let mut sound = None;
+    if gamepad::just_pressed(&gamepads, GamepadButton::East){
+        close(&mut commands, &editor_ui);
+        sound = Some(GameSoundType::Select);
+    }
+    for e in keyboard.read(){
+        if e.state == ButtonState::Released{
+            continue;
+        }
+        let line = editor.line;
+        match &e.logical_key{
+            Key::Character(c) => editor.lines[line].push_str(c),
+            Key::Space => editor.lines[line].push(' '),
+            Key::Backspace => {
+                editor.lines[line].pop();
+            },
+            Key::Delete => {
+                editor.lines[line].clear();
+                sound = Some(GameSoundType::Delete);
+            },
+            Key::ArrowUp => {
+                editor.line = line.saturating_sub(1);
+                sound = Some(if line > 0 {GameSoundType::Move} else {GameSoundType::CantMove});
+            },
+            Key::ArrowDown => {
+                //going past a last line with something on it starts a new one
+                if line + 1 == editor.lines.len() && !editor.lines[line].trim().is_empty(){
+                    editor.lines.push(String::new());
+                }
+                editor.line = (line + 1).min(editor.lines.len() - 1);
+                sound = Some(if editor.line != line {GameSoundType::Move} else {GameSoundType::CantMove});
+            },
+            Key::Enter => match editor.transitions(&sim.machine){
+                Ok(transitions) => {
+                    let mut machine = sim.machine.clone();
+                    machine.remove_edge(&editor.from, &editor.to);
+                    for (read, transition) in transitions{
+                        machine.add_transition(editor.from.clone(), read, transition);
+                    }
+                    if let Some(place) = editor.place{
+                        //everything else is pinned where it is, like when dragging, so nothing moves to make room
+                        machine.layout = sim.machine.positions().into_iter().map(|(state, point)| (state.to_string(), point)).collect();
+                        machine.layout.insert(editor.to.clone(), place);
+                    }
+                    sim.replace_machine(machine);
+                    close(&mut commands, &editor_ui);
+                    sound = Some(GameSoundType::Write);
+                    break;
+                },
+                Err(e) => {
+                    editor.error = Some(e);
+                    sound = Some(GameSoundType::CantMove);
+                },
+            },
+            Key::Escape => {
+                close(&mut commands, &editor_ui);
+                sound = Some(GameSoundType::Select);
+                break;
+            },
+            _ => (),
+        }
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///shows the arrow's transitions as lines of text
+pub fn spawn_editor(
+    mut commands: Commands,
+    editor: Res<EdgeEditor>,
+){
+    spawn_panel(&mut commands, EditorUI, Placement::Bottom, Some(&format!("Transitions from {} to {}", editor.from, editor.to)), |panel| {
+        panel.spawn((
+            EditorLines,
+            Text::new(""),
+            TextFont{
+                font_size: LINES_FONT_SIZE,
+                font_smoothing: FontSmoothing::AntiAliased,
+                ..Default::default()
+            },
+            BaseFontSize(LINES_FONT_SIZE),
+            TextColor(Color::WHITE),
+            TextLayout::new_with_justify(JustifyText::Left).with_no_wrap(),
+        ));
+        panel.spawn((EditorError, panel_text("", ERROR_FONT_SIZE))).insert(TextColor(ERROR_COLOR));
+    }, Some(HINT_TEXT));
+}
+
+///shows the lines with a caret on the one being typed on, and the last error
+pub fn update_editor(
+    editor: Res<EdgeEditor>,
+    mut lines: Query<&mut Text, (With<EditorLines>, Without<EditorError>)>,
+    mut errors: Query<&mut Text, (With<EditorError>, Without<EditorLines>)>,
+){
+    for mut text in &mut lines{
+        text.0 = editor.lines.iter().enumerate()
+            .map(|(i, line)| if i == editor.line {format!("{}{}{}", LINE_MARKER, line, CARET)} else {format!("{}{}", " ".repeat(LINE_MARKER.len()), line)})
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    for mut text in &mut errors{
+        text.0 = editor.error.clone().unwrap_or_default();
+    }
+}
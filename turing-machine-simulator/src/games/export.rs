@@ -0,0 +1,45 @@
+use std::{fs, path::{Path, PathBuf}};
+use bevy::{audio::PlaybackMode, prelude::*};
+use turing_core::{diagram, Machine};
+
+use crate::{actions::{Action, Actions}, games::{GameSoundType, GameSounds, MachineFile, SaveSlot, Sim}, paths::AppPaths, settings::{Bus, Settings}, toast::{show_toast, Toast}};
+
+///name of diagrams of a machine that isn't in a save or file
+const UNNAMED_DIAGRAM: &str = "machine";
+
+///writes `machine`'s diagram to `<name>.dot` and `<name>.svg` in `dir`, returning the svg's path
+fn write_diagrams(machine: &Machine, dir: &Path, name: &str) -> Result<PathBuf, String>{
+    fs::create_dir_all(dir).map_err(|e| format!("could not make {}: {}", dir.display(), e))?;
+    let dot_path = dir.join(format!("{}.dot", name));
+    fs::write(&dot_path, diagram::dot(machine, name)).map_err(|e| format!("could not write {}: {}", dot_path.display(), e))?;
+    let svg_path = dir.join(format!("{}.svg", name));
+    fs::write(&svg_path, diagram::svg(machine)).map_err(|e| format!("could not write {}: {}", svg_path.display(), e))?;
+    Ok(svg_path)
+}
+
+///writes the diagram of the machine being played into the diagrams folder
+#[allow(clippy::too_many_arguments)]
+pub fn export_diagram(
+    mut commands: Commands,
+    actions: Actions,
+    sim: Res<Sim>,
+    save_slot: Res<SaveSlot>,
+    machine_file: Res<MachineFile>,
+    paths: Res<AppPaths>,
+    toasts: Query<Entity, With<Toast>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    if !actions.just_pressed(Action::ExportDiagram){
+        return;
+    }
+    let name = save_slot.clone()
+        .or_else(|| machine_file.as_deref().and_then(Path::file_stem).map(|stem| stem.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| UNNAMED_DIAGRAM.to_string());
+    let (message, sound) = match write_diagrams(&sim.machine, &paths.diagrams_dir(), &name){
+        Ok(path) => (format!("Saved the diagram to {}", path.display()), GameSoundType::Select),
+        Err(e) => (format!("Couldn't save the diagram: {}", e), GameSoundType::CantMove),
+    };
+    show_toast(&mut commands, &toasts, message);
+    commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+}
@@ -0,0 +1,117 @@
+use bevy::{prelude::*, text::FontSmoothing, window::PrimaryWindow};
+use turing_core::Status;
+
+use crate::{games::{run::RunState, tapes_bottom, GameUI, Sim, CELL_SPACING_PER}, BaseFontSize};
+
+//panel
+const PANEL_COLOR: Color = Color::linear_rgba(0.0, 0.0, 0.0, 0.6);
+///gap between the bottom tape and the panel, in pixels
+const PANEL_GAP: f32 = 16.0;
+//text
+const FONT_SIZE: f32 = 20.0;
+const TEXT_COLOR: Color = Color::WHITE;
+const ACCEPT_COLOR: Color = Color::linear_rgb(0.2, 0.8, 0.3);
+const REJECT_COLOR: Color = Color::linear_rgb(0.9, 0.15, 0.15);
+const STUCK_COLOR: Color = Color::linear_rgb(1.0, 0.6, 0.0);
+
+///a line of the heads-up display
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HudField{
+    State,
+    Steps,
+    Head,
+    NonBlank,
+    Status,
+}
+
+impl HudField{
+    const ALL: [HudField; 5] = [HudField::State, HudField::Steps, HudField::Head, HudField::NonBlank, HudField::Status];
+}
+
+///marker for the heads-up display's panel
+#[derive(Component)]
+pub struct Hud;
+
+///spawns the panel showing how the machine is doing, filled in by [`update_hud`]
+pub fn spawn_hud(
+    mut commands: Commands,
+){
+    commands.spawn((
+        GameUI,
+        Hud,
+        Node{
+            position_type: PositionType::Absolute,
+            left: Val::Percent(CELL_SPACING_PER),
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Vw(0.8)),
+            ..Default::default()
+        },
+        BackgroundColor(PANEL_COLOR),
+        BorderRadius::all(Val::VMax(1.0)),
+    )).with_children(|panel| {
+        for field in HudField::ALL{
+            panel.spawn((
+                field,
+                Text::new(""),
+                TextFont{
+                    font_size: FONT_SIZE,
+                    font_smoothing: FontSmoothing::AntiAliased,
+                    ..Default::default()
+                },
+                BaseFontSize(FONT_SIZE),
+                TextColor(TEXT_COLOR),
+                TextLayout::new_with_justify(JustifyText::Left).with_no_wrap(),
+            ));
+        }
+    });
+}
+
+///one value per tape, left to right
+fn per_tape(values: impl Iterator<Item = String>) -> String{
+    values.collect::<Vec<_>>().join(", ")
+}
+
+///shows the machine's state, steps, heads, written cells and status, keeping the panel under the tapes
+pub fn update_hud(
+    sim: Res<Sim>,
+    run_state: Option<Res<State<RunState>>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut panels: Query<&mut Node, With<Hud>>,
+    mut fields: Query<(&HudField, &mut Text, &mut TextColor)>,
+){
+    if let Ok(window) = windows.single(){
+        let top = Val::Px(tapes_bottom(window, sim.tracks.len()) + PANEL_GAP);
+        for mut node in &mut panels{
+            if node.top != top{
+                node.top = top;
+            }
+        }
+    }
+
+    let heads = if sim.tracks.len() > 1 {"Heads"} else {"Head"};
+    let running = run_state.is_some_and(|state| **state == RunState::Running);
+    //worked out from the tapes rather than the last step, so writing a symbol by hand shows straight away
+    let status = sim.machine.status(&sim.state, &sim.read());
+    for (&field, mut text, mut color) in &mut fields{
+        let (line, new_color) = match field{
+            HudField::State => (format!("State: {}", sim.state), TEXT_COLOR),
+            HudField::Steps => (format!("Steps: {}", sim.steps), TEXT_COLOR),
+            HudField::Head => (format!("{}: {}", heads, per_tape(sim.tracks.iter().map(|track| track.head.to_string()))), TEXT_COLOR),
+            HudField::NonBlank => (format!("Non-blank cells: {}", per_tape(sim.tracks.iter().map(|track| track.tape.non_blank_count().to_string()))), TEXT_COLOR),
+            HudField::Status => match status{
+                Status::Running if running => ("Status: running".to_string(), TEXT_COLOR),
+                Status::Running => ("Status: running (paused)".to_string(), TEXT_COLOR),
+                Status::Accepted => ("Status: halted, accepted".to_string(), ACCEPT_COLOR),
+                Status::Rejected => ("Status: halted, rejected".to_string(), REJECT_COLOR),
+                Status::Stuck => ("Status: stuck, no transition".to_string(), STUCK_COLOR),
+            },
+        };
+        //only touched when something changed, so the text isn't laid out again every frame
+        if text.0 != line{
+            text.0 = line;
+        }
+        if color.0 != new_color{
+            color.0 = new_color;
+        }
+    }
+}
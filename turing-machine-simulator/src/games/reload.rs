@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+use bevy::{asset::AssetLoadFailedEvent, audio::PlaybackMode, prelude::*};
+use turing_core::{save, Simulation, Tape};
+
+use crate::{actions::{Action, Actions}, gamepad, games::{branch_picker::{BranchPicker, BranchUI}, edge_editor::{EdgeEditor, EditorUI}, transition_table::{TableUI, TransitionTable}, tree_view::{TreeUI, TreeView}, spawn_panel, GameSoundType, GameSounds, Placement, Sim}, machine_asset::MachineAsset, settings::{Bus, Settings}, toast::{show_toast, Toast}};
+
+const HINT_TEXT: &str = "Step: reset the tapes to the file's   Exit: keep the tapes";
+
+///the machine file being played, swapped in whenever it changes on disk
+#[derive(Resource)]
+pub struct WatchedMachine{
+    handle: Handle<MachineAsset>,
+    ///where the file is on disk
+    path: PathBuf,
+    ///whether the file is the program of a save, which keeps the save's alphabet unless the file gives one
+    from_save: bool,
+    ///false until the first version of the file is in the sandbox
+    applied: bool,
+}
+
+impl WatchedMachine{
+    ///a machine file that starts the sandbox once it loads
+    pub fn opening(handle: Handle<MachineAsset>, path: PathBuf) -> Self{
+        Self{
+            handle,
+            path,
+            from_save: false,
+            applied: false,
+        }
+    }
+
+    ///the program of a save that has already been read
+    pub fn program(handle: Handle<MachineAsset>, path: PathBuf) -> Self{
+        Self{
+            handle,
+            path,
+            from_save: true,
+            applied: true,
+        }
+    }
+}
+
+///tells the player edits to the machine have to be made in its file, since reloading it would undo them.
+///
+///returns whether the edit was refused
+pub fn refuse_edit(commands: &mut Commands, toasts: &Query<Entity, With<Toast>>, watched: Option<&WatchedMachine>) -> bool{
+    let Some(watched) = watched else{
+        return false;
+    };
+    show_toast(commands, toasts, format!("Edit {} to change the machine, it's reloaded from there", watched.path.display()));
+    true
+}
+
+///offers to reset the tapes after the machine was reloaded, open while this exists
+#[derive(Resource)]
+pub struct ReloadPrompt{
+    ///the tapes the reloaded file starts on
+    tapes: Vec<Tape>,
+    ///false on the frame the prompt opened
+    armed: bool,
+}
+
+///marker for the prompt's ui
+#[derive(Component)]
+pub struct ReloadUI;
+
+///puts the machine file into the sandbox when it first loads and again every time it changes
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn watch_machine(
+    mut commands: Commands,
+    mut watched: ResMut<WatchedMachine>,
+    mut events: EventReader<AssetEvent<MachineAsset>>,
+    mut failures: EventReader<AssetLoadFailedEvent<MachineAsset>>,
+    machines: Res<Assets<MachineAsset>>,
+    mut sim: ResMut<Sim>,
+    picker_ui: Query<Entity, Or<(With<BranchUI>, With<TreeUI>, With<EditorUI>, With<TableUI>)>>,
+    toasts: Query<Entity, With<Toast>>,
+){
+    let id = watched.handle.id();
+    for failure in failures.read(){
+        if failure.id != id{
+            continue;
+        }
+        error!("failed to load {}: {}", failure.path, failure.error);
+        show_toast(&mut commands, &toasts, format!("Couldn't load {}: {}", watched.path.display(), failure.error));
+    }
+
+    for event in events.read(){
+        let reloaded = match *event{
+            AssetEvent::LoadedWithDependencies{id: loaded} if loaded == id && !watched.applied => false,
+            AssetEvent::Modified{id: modified} if modified == id && watched.applied => true,
+            _ => continue,
+        };
+        let Some(machine) = machines.get(id) else{
+            continue;
+        };
+        let description = machine.0.clone();
+        if !reloaded{
+            sim.simulation = description.into_simulation();
+            watched.applied = true;
+            continue;
+        }
+
+        let tapes = description.tapes.clone();
+        let applied = if watched.from_save{
+            save::apply_program(&mut sim.simulation, description).map_err(|e| e.to_string())
+        }else{
+            let mut machine = description.machine;
+            machine.layout = sim.machine.layout.clone();
+            sim.replace_machine(machine);
+            Ok(())
+        };
+        if let Err(e) = applied{
+            show_toast(&mut commands, &toasts, format!("Couldn't reload {}: {}", watched.path.display(), e));
+            continue;
+        }
+        //their choices belong to the old machine
+        commands.remove_resource::<BranchPicker>();
+        commands.remove_resource::<TreeView>();
+        commands.remove_resource::<EdgeEditor>();
+        commands.remove_resource::<TransitionTable>();
+        for entity in &picker_ui{
+            commands.entity(entity).despawn();
+        }
+        commands.insert_resource(ReloadPrompt{tapes, armed: false});
+    }
+}
+
+///resets the tapes or keeps them
+#[allow(clippy::too_many_arguments)]
+pub fn prompt_controls(
+    mut commands: Commands,
+    mut prompt: ResMut<ReloadPrompt>,
+    actions: Actions,
+    gamepads: Query<&Gamepad>,
+    mut sim: ResMut<Sim>,
+    prompt_ui: Query<Entity, With<ReloadUI>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    if !prompt.armed{
+        prompt.armed = true;
+        return;
+    }
+    let reset = actions.just_pressed(Action::Step) || gamepad::just_pressed(&gamepads, GamepadButton::South);
+    let keep = actions.just_pressed(Action::Exit) || gamepad::just_pressed(&gamepads, GamepadButton::East);
+    if !reset && !keep{
+        return;
+    }
+    if reset{
+        let machine = sim.machine.clone();
+        sim.simulation = Simulation::new(prompt.tapes.clone(), machine);
+    }
+    commands.remove_resource::<ReloadPrompt>();
+    for entity in &prompt_ui{
+        commands.entity(entity).despawn();
+    }
+    let sound = if reset {GameSoundType::Delete} else {GameSoundType::Select};
+    commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+}
+
+///tells the player the machine changed
+pub fn spawn_prompt(
+    mut commands: Commands,
+    watched: Res<WatchedMachine>,
+){
+    let name = watched.path.file_name().unwrap_or(watched.path.as_os_str()).to_string_lossy();
+    spawn_panel(&mut commands, ReloadUI, Placement::Bottom, Some(&format!("{} changed, the machine was reloaded", name)), |_| (), Some(HINT_TEXT));
+}
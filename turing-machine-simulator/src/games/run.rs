@@ -0,0 +1,162 @@
+use std::time::{Duration, Instant};
+use bevy::{audio::PlaybackMode, prelude::*};
+use turing_core::Status;
+
+use crate::{actions::{Action, Actions}, games::{branch_picker::BranchPicker, GameSoundType, GameSounds, Sim}, settings::{Bus, Settings}, AppState};
+
+/// steps per second for each speed setting, `None` runs as fast as possible
+const SPEEDS: [Option<f64>; 8] = [Some(1.0), Some(2.0), Some(4.0), Some(8.0), Some(16.0), Some(64.0), Some(256.0), None];
+const DEFAULT_SPEED_INDEX: usize = 2;
+/// how long a frame may spend stepping when running as fast as possible
+const MAX_FRAME_BUDGET: Duration = Duration::from_millis(10);
+/// steps taken between checks of the frame budget
+const STEPS_PER_BUDGET_CHECK: u32 = 1_000;
+
+/// whether the machine is stepping on its own
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(AppState = AppState::InGame)]
+pub(super) enum RunState{
+    #[default]
+    Paused,
+    Running,
+}
+
+/// how quickly the machine steps while running
+#[derive(Resource)]
+pub struct RunSpeed{
+    index: usize,
+    /// fractional steps carried over between frames
+    owed_steps: f64,
+}
+
+impl Default for RunSpeed{
+    fn default() -> Self {
+        Self{
+            index: DEFAULT_SPEED_INDEX,
+            owed_steps: 0.0,
+        }
+    }
+}
+
+impl RunSpeed{
+    /// steps per second, `None` when running as fast as possible
+    pub fn steps_per_second(&self) -> Option<f64>{
+        SPEEDS[self.index]
+    }
+}
+
+///handles run, pause, step, step back and speed inputs
+#[allow(clippy::too_many_arguments)]
+pub fn run_controls(
+    actions: Actions,
+    mut sim: ResMut<Sim>,
+    mut speed: ResMut<RunSpeed>,
+    run_state: Res<State<RunState>>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+    mut commands: Commands,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    let volume = settings.volume(Bus::TapeEffects);
+    let mut sound = None;
+    let step = actions.just_pressed(Action::Step);
+    let step_back = actions.just_pressed(Action::StepBack);
+    let run = actions.just_pressed(Action::Run);
+
+    //the tapes may have been written on by hand since the last step, which can unstick the machine or stop it
+    if run || step{
+        sim.update_status();
+    }
+
+    if run{
+        match **run_state{
+            RunState::Paused if sim.status == Status::Running => {
+                speed.owed_steps = 0.0;
+                next_run_state.set(RunState::Running);
+                sound = Some(GameSoundType::Select);
+            },
+            RunState::Paused => sound = Some(GameSoundType::CantMove),
+            RunState::Running => {
+                next_run_state.set(RunState::Paused);
+                sound = Some(GameSoundType::Select);
+            },
+        }
+    }
+
+    if step && !step_back && sim.status == Status::Running && sim.choices().len() > 1{
+        //the player picks which way a nondeterministic step goes
+        next_run_state.set(RunState::Paused);
+        commands.insert_resource(BranchPicker::default());
+        sound = Some(GameSoundType::Select);
+    }else if step || step_back{
+        next_run_state.set(RunState::Paused);
+        let moved = if step_back{
+            sim.step_back()
+        }else{
+            let initial_steps = sim.steps;
+            sim.step();
+            sim.steps != initial_steps
+        };
+        sound = Some(if moved {GameSoundType::Select} else {GameSoundType::CantMove});
+    }
+
+    if actions.just_pressed(Action::SpeedUp){
+        sound = Some(if speed.index + 1 < SPEEDS.len() {speed.index += 1; GameSoundType::Move} else {GameSoundType::CantMove});
+    }
+    if actions.just_pressed(Action::SpeedDown){
+        sound = Some(if speed.index > 0 {speed.index -= 1; GameSoundType::Move} else {GameSoundType::CantMove});
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///steps the machine according to the elapsed time rather than the frame rate
+pub fn run_machine(
+    mut sim: ResMut<Sim>,
+    mut speed: ResMut<RunSpeed>,
+    time: Res<Time>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+    mut commands: Commands,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    //running stops wherever the machine has a choice to make
+    let at_branch = |sim: &Sim| sim.choices().len() > 1;
+    match speed.steps_per_second(){
+        Some(steps_per_second) => {
+            speed.owed_steps += time.delta_secs_f64() * steps_per_second;
+            while speed.owed_steps >= 1.0 && sim.status == Status::Running && !at_branch(&sim){
+                speed.owed_steps -= 1.0;
+                sim.step();
+            }
+        },
+        None => {
+            let start = Instant::now();
+            'run: while sim.status == Status::Running && start.elapsed() < MAX_FRAME_BUDGET{
+                for _ in 0..STEPS_PER_BUDGET_CHECK{
+                    if at_branch(&sim){
+                        break 'run;
+                    }
+                    if sim.step() != Status::Running{
+                        break;
+                    }
+                }
+            }
+        },
+    }
+
+    if sim.status == Status::Running && at_branch(&sim){
+        next_run_state.set(RunState::Paused);
+        commands.insert_resource(BranchPicker::default());
+        commands.spawn((AudioPlayer::new(sounds[&GameSoundType::Select].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+        return;
+    }
+
+    if sim.status != Status::Running{
+        next_run_state.set(RunState::Paused);
+        let sound = if sim.status == Status::Accepted {GameSoundType::Select} else {GameSoundType::CantMove};
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+    }
+}
@@ -1,20 +1,66 @@
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use bevy::prelude::*;
+use turing_core::save::{self, Metadata, SaveError};
+use turing_core::{slots, Simulation};
 
-use crate::games::{SaveFileIndex, Tape, SAVE_FILE_PATH};
+use crate::games::{reload::WatchedMachine, save_error::SaveErrorDialog, MachineFile, SaveSlot, Sim};
+use crate::machine_asset::{asset_path, MACHINE_SOURCE, SAVES_SOURCE};
+use crate::paths::AppPaths;
+use crate::toast::{show_toast, Toast};
+
+/// path of the save called `name`
+pub fn save_path(paths: &AppPaths, name: &str) -> PathBuf{
+    slots::slot_path(&paths.saves_dir(), name)
+}
+
+/// reads the world called `name`
+fn read(paths: &AppPaths, name: &str) -> Result<(Simulation, Metadata), SaveError>{
+    save::read_world(&save_path(paths, name))
+}
+
+/// puts the world called `name` in the sandbox, watching its program if it has one
+pub fn open(commands: &mut Commands, paths: &AppPaths, asset_server: &AssetServer, sim: &mut Sim, name: &str) -> Result<(), SaveError>{
+    (sim.simulation, sim.metadata) = read(paths, name)?;
+    //the save's program is watched so editing it changes the machine straight away
+    let program_path = save::program_path(&save_path(paths, name));
+    if let Some(file_name) = program_path.file_name().filter(|_| program_path.is_file()){
+        let handle = asset_server.load(asset_path(SAVES_SOURCE, Path::new(file_name)));
+        commands.insert_resource(WatchedMachine::program(handle, program_path.clone()));
+    }
+    Ok(())
+}
 
 pub fn load(
-    mut _commands: Commands,
-    save_file_index: Res<SaveFileIndex>,
-    mut tape: ResMut<Tape>,
+    mut commands: Commands,
+    mut save_slot: ResMut<SaveSlot>,
+    machine_file: Res<MachineFile>,
+    mut sim: ResMut<Sim>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+    asset_server: Res<AssetServer>,
 ){
-    let contents = match fs::read_to_string(Path::new(&format!("{}{}.sav", SAVE_FILE_PATH, save_file_index.clone().unwrap()))){
-        Ok(s) => s,
-        Err(_) => "ERROR LOADING FILE".to_string(),
-    };
+    //a machine file starts fresh from its initial tapes once it loads and is never saved over
+    if let Some(path) = &**machine_file{
+        let Some(file_name) = path.file_name() else{
+            show_toast(&mut commands, &toasts, format!("{} is not a file", path.display()));
+            return;
+        };
+        let handle = asset_server.load(asset_path(MACHINE_SOURCE, Path::new(file_name)));
+        commands.insert_resource(WatchedMachine::opening(handle, path.clone()));
+        return;
+    }
 
-    for (i, c) in contents.char_indices(){
-        tape[i] = c;
+    let name = save_slot.clone().unwrap();
+    match open(&mut commands, &paths, &asset_server, &mut sim, &name){
+        Ok(()) => (),
+        Err(SaveError::NotFound(_)) => {
+            *sim = Sim::default();
+            show_toast(&mut commands, &toasts, format!("{} is empty, starting a new world", name));
+        },
+        Err(e) => {
+            //nothing gets saved until the player decides what to do
+            **save_slot = None;
+            commands.insert_resource(SaveErrorDialog::new(e, name));
+        },
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,221 @@
+use bevy::{audio::PlaybackMode, prelude::*, text::FontSmoothing};
+use turing_core::save::{self, SaveError};
+
+use crate::{gamepad, games::{panel_text, sandbox, spawn_panel, GameSoundType, GameSounds, GameState, Placement, SaveSlot, Sim}, menus::MenuState, paths::AppPaths, settings::{Bus, Settings}, toast::{show_toast, Toast}, AppState, BaseFontSize};
+
+//panel
+const PANEL_WIDTH_PER: f32 = 80.0;
+const PANEL_HEIGHT_PER: f32 = 50.0;
+//text
+const MESSAGE_FONT_SIZE: f32 = 26.0;
+//buttons
+const BUTTON_TEXT: [&str; 3] = ["Retry", "Start Fresh", "Back"];
+const BUTTON_WIDTH_PER: f32 = 28.0;
+const BUTTON_HEIGHT_PER: f32 = 20.0;
+const BUTTON_UNSELECTED_COLOR: Color = Color::linear_rgb(0.25, 0.25, 0.25);
+const BUTTON_SELECTED_COLOR: Color = Color::linear_rgb(1.0, 1.0, 1.0);
+const BUTTON_OUTLINE_UNSELECTED_WIDTH_PER: f32 = 0.5;
+const BUTTON_OUTLINE_SELECTED_WIDTH_PER: f32 = 1.0;
+const BUTTON_TEXT_COLOR: Color = Color::BLACK;
+const BUTTON_OUTLINE_COLOR: Color = Color::BLACK;
+const BUTTON_TEXT_FONT_SIZE: f32 = 30.0;
+
+///shown instead of the game when a save couldn't be loaded
+#[derive(Resource)]
+pub struct SaveErrorDialog{
+    error: SaveError,
+    save_slot: String,
+    selected: usize,
+}
+
+impl SaveErrorDialog{
+    pub fn new(error: SaveError, save_slot: String) -> Self{
+        Self{
+            error,
+            save_slot,
+            selected: 0,
+        }
+    }
+}
+
+///marker for the dialog's ui
+#[derive(Component)]
+pub struct DialogUI;
+
+///marker for the text holding the error message
+#[derive(Component)]
+pub struct DialogMessage;
+
+#[derive(Component, Deref)]
+pub struct DialogButton(usize);
+
+///spawns the dialog when a save fails to load
+pub fn spawn_dialog(
+    mut commands: Commands,
+    dialog: Res<SaveErrorDialog>,
+){
+    let title = format!("Couldn't load {}", dialog.save_slot);
+    spawn_panel(&mut commands, DialogUI, Placement::Centered{width: PANEL_WIDTH_PER, height: PANEL_HEIGHT_PER}, Some(&title), |panel| {
+        panel.spawn((DialogMessage, panel_text(dialog.error.to_string(), MESSAGE_FONT_SIZE)));
+        panel.spawn(Node{
+            width: Val::Percent(100.0),
+            height: Val::Percent(BUTTON_HEIGHT_PER),
+            justify_content: JustifyContent::SpaceEvenly,
+            ..Default::default()
+        }).with_children(|row| {
+            for (i, &text) in BUTTON_TEXT.iter().enumerate(){
+                row.spawn((
+                    Button,
+                    DialogButton(i),
+                    Node{
+                        width: Val::Percent(BUTTON_WIDTH_PER),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    BackgroundColor(BUTTON_UNSELECTED_COLOR),
+                    BorderRadius::all(Val::VMax(5.0)),
+                    Outline::new(Val::Percent(BUTTON_OUTLINE_UNSELECTED_WIDTH_PER), Val::Px(0.0), BUTTON_OUTLINE_COLOR),
+                )).with_child((
+                    Text::new(text),
+                    TextFont{
+                        font_size: BUTTON_TEXT_FONT_SIZE,
+                        font_smoothing: FontSmoothing::AntiAliased,
+                        ..Default::default()
+                    },
+                    BaseFontSize(BUTTON_TEXT_FONT_SIZE),
+                    TextColor(BUTTON_TEXT_COLOR),
+                    TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+                ));
+            }
+        });
+    }, None);
+}
+
+///highlights the selected button and shows the latest error
+pub fn update_dialog(
+    dialog: Res<SaveErrorDialog>,
+    mut buttons: Query<(&DialogButton, &mut BackgroundColor, &mut Outline)>,
+    mut message: Query<&mut Text, With<DialogMessage>>,
+){
+    for (index, mut bgc, mut outline) in &mut buttons{
+        if **index == dialog.selected{
+            bgc.0 = BUTTON_SELECTED_COLOR;
+            outline.width = Val::Percent(BUTTON_OUTLINE_SELECTED_WIDTH_PER);
+        }else{
+            bgc.0 = BUTTON_UNSELECTED_COLOR;
+            outline.width = Val::Percent(BUTTON_OUTLINE_UNSELECTED_WIDTH_PER);
+        }
+    }
+    for mut text in &mut message{
+        text.0 = dialog.error.to_string();
+    }
+}
+
+///handles inputs while the dialog is open
+#[allow(clippy::too_many_arguments)]
+pub fn dialog_controls(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut dialog: ResMut<SaveErrorDialog>,
+    mut save_slot: ResMut<SaveSlot>,
+    mut sim: ResMut<Sim>,
+    dialog_ui: Query<Entity, With<DialogUI>>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+    buttons: Query<(&DialogButton, &Interaction), Changed<Interaction>>,
+    gamepads: Query<&Gamepad>,
+    asset_server: Res<AssetServer>,
+){
+    let volume = settings.volume(Bus::TapeEffects);
+    let mut sound = None;
+    let mut clicked = None;
+    for (index, interaction) in &buttons{
+        match interaction{
+            Interaction::Hovered if **index != dialog.selected => {
+                dialog.selected = **index;
+                sound = Some(GameSoundType::Move);
+            },
+            Interaction::Pressed => clicked = Some(**index),
+            _ => (),
+        }
+    }
+    let previous = [GamepadButton::DPadLeft, GamepadButton::DPadUp].into_iter().any(|b| gamepad::just_pressed(&gamepads, b));
+    let next = [GamepadButton::DPadRight, GamepadButton::DPadDown].into_iter().any(|b| gamepad::just_pressed(&gamepads, b));
+    if inputs.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowUp]) || previous{
+        dialog.selected = dialog.selected.checked_sub(1).unwrap_or(BUTTON_TEXT.len() - 1);
+        sound = Some(GameSoundType::Move);
+    }
+    if inputs.any_just_pressed([KeyCode::ArrowRight, KeyCode::ArrowDown]) || next{
+        dialog.selected = (dialog.selected + 1) % BUTTON_TEXT.len();
+        sound = Some(GameSoundType::Move);
+    }
+
+    let choice = if inputs.just_pressed(KeyCode::Escape) || gamepad::just_pressed(&gamepads, GamepadButton::East){
+        Some(BUTTON_TEXT.len() - 1)
+    }else if inputs.just_pressed(KeyCode::Enter) || gamepad::just_pressed(&gamepads, GamepadButton::South){
+        Some(dialog.selected)
+    }else{
+        clicked
+    };
+
+    let close = match choice{
+        //retry
+        Some(0) => match sandbox::open(&mut commands, &paths, &asset_server, &mut sim, &dialog.save_slot){
+            Ok(()) => {
+                sound = Some(GameSoundType::Select);
+                true
+            },
+            Err(e) => {
+                dialog.error = e;
+                sound = Some(GameSoundType::CantMove);
+                false
+            },
+        },
+        //start fresh, keeping the broken save around
+        Some(1) => match save::back_up(&sandbox::save_path(&paths, &dialog.save_slot)){
+            Ok(backup) => {
+                *sim = Sim::default();
+                show_toast(&mut commands, &toasts, format!("The old save was moved to {}", backup.display()));
+                sound = Some(GameSoundType::Select);
+                true
+            },
+            Err(SaveError::NotFound(_)) => {
+                *sim = Sim::default();
+                sound = Some(GameSoundType::Select);
+                true
+            },
+            Err(e) => {
+                dialog.error = e;
+                sound = Some(GameSoundType::CantMove);
+                false
+            },
+        },
+        //back to the menu without saving
+        Some(_) => {
+            next_game_state.set(GameState::None);
+            next_app_state.set(AppState::Transition);
+            next_menu_state.set(MenuState::SandboxMenu);
+            commands.remove_resource::<SaveErrorDialog>();
+            return;
+        },
+        None => false,
+    };
+
+    if close{
+        **save_slot = Some(dialog.save_slot.clone());
+        commands.remove_resource::<SaveErrorDialog>();
+        for entity in &dialog_ui{
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::{audio::PlaybackMode, prelude::*, text::FontSmoothing};
+
+use crate::{gamepad, games::{spawn_panel, GameSoundType, GameSounds, Placement, Sim}, settings::{Bus, Settings}, BaseFontSize};
+
+//symbols
+const SYMBOL_SIZE_PER: f32 = 6.0;
+const SYMBOL_FONT_SIZE: f32 = 40.0;
+const SYMBOL_UNSELECTED_COLOR: Color = Color::linear_rgb(0.25, 0.25, 0.25);
+const SYMBOL_SELECTED_COLOR: Color = Color::WHITE;
+const SYMBOL_TEXT_COLOR: Color = Color::BLACK;
+//hint
+const HINT_TEXT: &str = "Left/Right: choose   South: write   East: cancel";
+
+///row of symbols to write with a gamepad, open while this exists
+#[derive(Resource)]
+pub struct SymbolPicker{
+    symbols: Vec<char>,
+    selected: usize,
+}
+
+impl SymbolPicker{
+    ///offers the machine's tape alphabet, starting at the symbol under the head.
+    ///the blank is left out, erasing does that
+    fn new(sim: &Sim) -> Self{
+        let blank = sim.machine.alphabet.blank();
+        let symbols: Vec<char> = sim.machine.alphabet.tape()
+            .filter(|&c| c != blank)
+            .collect();
+        let selected = symbols.iter().position(|&c| c == sim.track().read()).unwrap_or(0);
+        Self{
+            symbols,
+            selected,
+        }
+    }
+}
+
+///marker for the picker's ui
+#[derive(Component)]
+pub struct PickerUI;
+
+#[derive(Component, Deref)]
+pub struct PickerSymbol(usize);
+
+///opens the picker with the south face button, then chooses and writes a symbol with it
+pub fn picker_controls(
+    mut commands: Commands,
+    picker: Option<ResMut<SymbolPicker>>,
+    gamepads: Query<&Gamepad>,
+    mut sim: ResMut<Sim>,
+    picker_ui: Query<Entity, With<PickerUI>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    let volume = settings.volume(Bus::TapeEffects);
+    let mut sound = None;
+    match picker{
+        None => {
+            if gamepad::just_pressed(&gamepads, GamepadButton::South){
+                let picker = SymbolPicker::new(&sim);
+                //an alphabet of only the blank has nothing to offer
+                if picker.symbols.is_empty(){
+                    sound = Some(GameSoundType::CantMove);
+                }else{
+                    spawn_picker(&mut commands, &picker);
+                    commands.insert_resource(picker);
+                    sound = Some(GameSoundType::Select);
+                }
+            }
+        },
+        Some(mut picker) => {
+            let count = picker.symbols.len();
+            if gamepad::just_pressed(&gamepads, GamepadButton::DPadLeft){
+                picker.selected = picker.selected.checked_sub(1).unwrap_or(count - 1);
+                sound = Some(GameSoundType::Move);
+            }
+            if gamepad::just_pressed(&gamepads, GamepadButton::DPadRight){
+                picker.selected = (picker.selected + 1) % count;
+                sound = Some(GameSoundType::Move);
+            }
+
+            let write = gamepad::just_pressed(&gamepads, GamepadButton::South);
+            if write{
+                let symbol = picker.symbols[picker.selected];
+                sim.track_mut().write(symbol);
+                sound = Some(GameSoundType::Write);
+            }
+            if write || gamepad::just_pressed(&gamepads, GamepadButton::East){
+                commands.remove_resource::<SymbolPicker>();
+                for entity in &picker_ui{
+                    commands.entity(entity).despawn();
+                }
+            }
+        },
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+fn spawn_picker(
+    commands: &mut Commands,
+    picker: &SymbolPicker,
+){
+    spawn_panel(commands, PickerUI, Placement::Bottom, None, |panel| {
+        panel.spawn(Node{
+            flex_wrap: FlexWrap::Wrap,
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Percent(1.0),
+            row_gap: Val::Percent(1.0),
+            ..Default::default()
+        }).with_children(|row| {
+            for (i, &symbol) in picker.symbols.iter().enumerate(){
+                row.spawn((
+                    PickerSymbol(i),
+                    Node{
+                        width: Val::Vw(SYMBOL_SIZE_PER),
+                        height: Val::Vw(SYMBOL_SIZE_PER),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    BackgroundColor(SYMBOL_UNSELECTED_COLOR),
+                    BorderRadius::all(Val::VMax(1.0)),
+                )).with_child((
+                    Text::new(symbol.to_string()),
+                    TextFont{
+                        font_size: SYMBOL_FONT_SIZE,
+                        font_smoothing: FontSmoothing::AntiAliased,
+                        ..Default::default()
+                    },
+                    BaseFontSize(SYMBOL_FONT_SIZE),
+                    TextColor(SYMBOL_TEXT_COLOR),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                ));
+            }
+        });
+    }, Some(HINT_TEXT));
+}
+
+///highlights the selected symbol
+pub fn update_picker(
+    picker: Res<SymbolPicker>,
+    mut symbols: Query<(&PickerSymbol, &mut BackgroundColor)>,
+){
+    for (index, mut bgc) in &mut symbols{
+        bgc.0 = if **index == picker.selected {SYMBOL_SELECTED_COLOR} else {SYMBOL_UNSELECTED_COLOR};
+    }
+}
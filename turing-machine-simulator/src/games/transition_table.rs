@@ -0,0 +1,397 @@
+use bevy::{audio::PlaybackMode, input::{keyboard::{Key, KeyboardInput}, ButtonState}, prelude::*, text::FontSmoothing};
+use turing_core::{Machine, Move, Transition};
+
+use crate::{actions::{Action, Actions}, gamepad, games::{panel_text, reload::{self, WatchedMachine}, run::RunState, spawn_panel, GameSoundType, GameSounds, Placement, Sim}, settings::{Bus, Settings}, toast::Toast, BaseFontSize};
+
+//panel
+const PANEL_WIDTH_PER: f32 = 90.0;
+const PANEL_HEIGHT_PER: f32 = 85.0;
+//text
+const TITLE_TEXT: &str = "Transition Table";
+const CELL_FONT_SIZE: f32 = 20.0;
+const ERROR_FONT_SIZE: f32 = 20.0;
+const ERROR_COLOR: Color = Color::linear_rgb(1.0, 0.3, 0.3);
+const BROWSE_HINT: &str = "Arrows: move   Enter: edit   Delete: clear   Exit: close\nOutlined: the cell the machine takes next";
+const EDIT_HINT: &str = "write/move/next, choices split by |   Enter: save   Escape: cancel";
+//cells
+const VISIBLE_ROWS: usize = 12;
+const VISIBLE_COLUMNS: usize = 7;
+const CELL_GAP_PER: f32 = 0.5;
+const HEADER_COLOR: Color = Color::linear_rgb(0.15, 0.15, 0.15);
+const CELL_COLOR: Color = Color::linear_rgb(0.05, 0.05, 0.05);
+const SELECTED_COLOR: Color = Color::WHITE;
+const SELECTED_TEXT_COLOR: Color = Color::BLACK;
+const TEXT_COLOR: Color = Color::WHITE;
+///outlines the cell the machine will take next
+const NEXT_COLOR: Color = Color::linear_rgb(1.0, 0.75, 0.0);
+const NEXT_OUTLINE_PX: f32 = 3.0;
+///most columns shown on a machine with several tapes before only the symbols its transitions read are
+const MAX_COLUMNS: usize = 256;
+//cell text
+const FIELD_SEPARATOR: char = '/';
+const CHOICE_SEPARATOR: &str = " | ";
+const CARET: char = '_';
+
+///the machine's transitions as a grid of states by symbols read, open while this exists
+#[derive(Resource, Default)]
+pub struct TransitionTable{
+    row: usize,
+    column: usize,
+    ///first row and column on screen
+    scroll: (usize, usize),
+    ///text of the cell being edited
+    editing: Option<String>,
+    error: Option<String>,
+    ///text that was warned about naming new states, entering it again adds them
+    confirmed: Option<String>,
+    ///false on the frame the table opened
+    armed: bool,
+}
+
+///marker for the table's ui
+#[derive(Component)]
+pub struct TableUI;
+
+fn move_letter(movement: Move) -> char{
+    match movement{
+        Move::Left => 'L',
+        Move::Right => 'R',
+        Move::Stay => 'S',
+    }
+}
+
+///states that can take transitions, the start state first. the accept and reject states halt the machine
+fn rows(machine: &Machine) -> Vec<&str>{
+    let mut rows: Vec<&str> = machine.states().into_iter()
+        .filter(|&state| state != machine.accept_state && state != machine.reject_state)
+        .collect();
+    rows.sort_by_key(|&state| state != machine.start_state);
+    rows
+}
+
+///the symbols each column reads, one per tape. every combination of the tape alphabet,
+///unless there are too many, then just those the transitions read
+fn columns(machine: &Machine) -> Vec<Vec<char>>{
+    let symbols: Vec<char> = machine.alphabet.tape().collect();
+    let count = (symbols.len() as u128).checked_pow(machine.tape_count as u32);
+    if count.is_none_or(|count| count > MAX_COLUMNS as u128){
+        let mut read: Vec<Vec<char>> = machine.transitions.keys().map(|(_, read)| read.clone()).collect();
+        read.sort();
+        read.dedup();
+        return read;
+    }
+    let mut columns = vec![Vec::new()];
+    for _ in 0..machine.tape_count{
+        columns = columns.into_iter()
+            .flat_map(|column| symbols.iter().map(move |&c| {
+                let mut column = column.clone();
+                column.push(c);
+                column
+            }))
+            .collect();
+    }
+    columns
+}
+
+///a cell's choices as `write/move/next`, split by `|`
+fn cell_text(choices: &[Transition]) -> String{
+    choices.iter()
+        .map(|transition| format!("{}{}{}{}{}",
+            transition.write.iter().collect::<String>(), FIELD_SEPARATOR,
+            transition.movement.iter().map(|&movement| move_letter(movement)).collect::<String>(), FIELD_SEPARATOR,
+            transition.next_state))
+        .collect::<Vec<_>>()
+        .join(CHOICE_SEPARATOR)
+}
+
+///reads a cell's text back into choices, along with the states they go to that the machine doesn't have yet
+fn parse_cell(text: &str, machine: &Machine) -> Result<(Vec<Transition>, Vec<String>), String>{
+    let states = machine.states();
+    let mut choices = Vec::new();
+    let mut new_states = Vec::new();
+    for choice in text.split(CHOICE_SEPARATOR.trim()).map(str::trim).filter(|choice| !choice.is_empty()){
+        let fields: Vec<&str> = choice.split(FIELD_SEPARATOR).map(str::trim).collect();
+        let &[write, moves, next_state] = &fields[..] else{
+            return Err(format!("\"{}\" should be write/move/next", choice));
+        };
+        let write: Vec<char> = write.chars().collect();
+        if write.len() != machine.tape_count{
+            return Err(format!("expected {} write symbols, one per tape, found {}", machine.tape_count, write.len()));
+        }
+        if let Some(c) = write.iter().find(|&&c| !machine.alphabet.is_tape(c)){
+            return Err(format!("'{}' isn't in the tape alphabet", c));
+        }
+        let movement = moves.chars()
+            .map(|c| Move::parse(&c.to_string()).ok_or(format!("unknown move '{}', use L, R or S", c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if movement.len() != machine.tape_count{
+            return Err(format!("expected {} moves, one per tape, found {}", machine.tape_count, movement.len()));
+        }
+        if next_state.is_empty() || next_state.contains(char::is_whitespace){
+            return Err(format!("\"{}\" isn't a state name", next_state));
+        }
+        if !states.contains(next_state) && !new_states.iter().any(|state| state == next_state){
+            new_states.push(next_state.to_string());
+        }
+        choices.push(Transition{next_state: next_state.to_string(), write, movement});
+    }
+    Ok((choices, new_states))
+}
+
+///moves `scroll` so `index` is among the `visible` items shown from it
+fn scroll_to(scroll: usize, index: usize, visible: usize) -> usize{
+    scroll.min(index).max((index + 1).saturating_sub(visible))
+}
+
+///opens the table, moves around it, and edits cells unless the machine is reloaded from a file
+#[allow(clippy::too_many_arguments)]
+pub fn table_controls(
+    mut commands: Commands,
+    table: Option<ResMut<TransitionTable>>,
+    actions: Actions,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut keyboard: EventReader<KeyboardInput>,
+    mut sim: ResMut<Sim>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+    table_ui: Query<Entity, With<TableUI>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+    watched: Option<Res<WatchedMachine>>,
+    toasts: Query<Entity, With<Toast>>,
+){
+    let mut sound = None;
+    match table{
+        None => {
+            keyboard.clear();
+            if actions.just_pressed(Action::TransitionTable){
+                next_run_state.set(RunState::Paused);
+                commands.insert_resource(TransitionTable::default());
+                sound = Some(GameSoundType::Select);
+            }
+        },
+        Some(mut table) if !table.armed => {
+            keyboard.clear();
+            table.armed = true;
+        },
+        Some(mut table) => {
+            let row_names: Vec<String> = rows(&sim.machine).into_iter().map(str::to_string).collect();
+            let columns = columns(&sim.machine);
+            let selected = row_names.get(table.row).zip(columns.get(table.column));
+            match table.editing.clone(){
+                Some(mut text) => {
+                    let mut keep = true;
+                    for e in keyboard.read(){
+                        if e.state == ButtonState::Released{
+                            continue;
+                        }
+                        match &e.logical_key{
+                            Key::Character(c) => text.push_str(c),
+                            Key::Space => text.push(' '),
+                            Key::Backspace => {
+                                text.pop();
+                            },
+                            Key::Delete => text.clear(),
+                            Key::Escape => {
+                                keep = false;
+                                sound = Some(GameSoundType::Select);
+                                break;
+                            },
+                            Key::Enter => {
+                                let Some((state, read)) = selected else{
+                                    keep = false;
+                                    break;
+                                };
+                                match parse_cell(&text, &sim.machine){
+                                    Ok((_, new_states)) if !new_states.is_empty() && table.confirmed.as_ref() != Some(&text) => {
+                                        table.error = Some(format!("{} isn't a state yet, Enter again to add it", new_states.join(", ")));
+                                        table.confirmed = Some(text.clone());
+                                        sound = Some(GameSoundType::CantMove);
+                                    },
+                                    Ok((choices, _)) => {
+                                        let mut machine = sim.machine.clone();
+                                        machine.transitions.remove(&(state.clone(), read.clone()));
+                                        for transition in choices{
+                                            machine.add_transition(state.clone(), read.clone(), transition);
+                                        }
+                                        sim.replace_machine(machine);
+                                        keep = false;
+                                        sound = Some(GameSoundType::Write);
+                                    },
+                                    Err(e) => {
+                                        table.error = Some(e);
+                                        sound = Some(GameSoundType::CantMove);
+                                    },
+                                }
+                                break;
+                            },
+                            _ => (),
+                        }
+                    }
+                    if !keep{
+                        table.editing = None;
+                        table.error = None;
+                        table.confirmed = None;
+                    }else if table.editing.as_ref() != Some(&text){
+                        table.editing = Some(text);
+                    }
+                },
+                None => {
+                    keyboard.clear();
+                    let (mut row, mut column) = (table.row, table.column);
+                    let pressed = |key, button| keys.just_pressed(key) || gamepad::just_pressed(&gamepads, button);
+                    let moved = [
+                        (KeyCode::ArrowUp, GamepadButton::DPadUp),
+                        (KeyCode::ArrowDown, GamepadButton::DPadDown),
+                        (KeyCode::ArrowLeft, GamepadButton::DPadLeft),
+                        (KeyCode::ArrowRight, GamepadButton::DPadRight),
+                    ].into_iter().any(|(key, button)| pressed(key, button));
+                    if pressed(KeyCode::ArrowUp, GamepadButton::DPadUp){
+                        row = row.saturating_sub(1);
+                    }
+                    if pressed(KeyCode::ArrowDown, GamepadButton::DPadDown){
+                        row = (row + 1).min(row_names.len().saturating_sub(1));
+                    }
+                    if pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft){
+                        column = column.saturating_sub(1);
+                    }
+                    if pressed(KeyCode::ArrowRight, GamepadButton::DPadRight){
+                        column = (column + 1).min(columns.len().saturating_sub(1));
+                    }
+                    if (row, column) != (table.row, table.column){
+                        table.row = row;
+                        table.column = column;
+                        table.scroll = (scroll_to(table.scroll.0, row, VISIBLE_ROWS), scroll_to(table.scroll.1, column, VISIBLE_COLUMNS));
+                        sound = Some(GameSoundType::Move);
+                    }else if moved{
+                        sound = Some(GameSoundType::CantMove);
+                    }
+
+                    let edit = keys.just_pressed(KeyCode::Enter);
+                    let delete = keys.just_pressed(KeyCode::Delete) || gamepad::just_pressed(&gamepads, GamepadButton::West);
+                    //cells are typed, so only the keyboard edits them
+                    if (edit || delete) && reload::refuse_edit(&mut commands, &toasts, watched.as_deref()){
+                        sound = Some(GameSoundType::CantMove);
+                    }else if edit{
+                        match selected{
+                            Some((state, read)) => {
+                                table.editing = Some(cell_text(sim.machine.choices(state, read)));
+                                sound = Some(GameSoundType::Select);
+                            },
+                            None => sound = Some(GameSoundType::CantMove),
+                        }
+                    }else if delete{
+                        if let Some((state, read)) = selected{
+                            let mut machine = sim.machine.clone();
+                            machine.transitions.remove(&(state.clone(), read.clone()));
+                            sim.replace_machine(machine);
+                        }
+                        sound = Some(GameSoundType::Delete);
+                    }else if actions.just_pressed(Action::Exit) || actions.just_pressed(Action::TransitionTable) || gamepad::just_pressed(&gamepads, GamepadButton::East){
+                        commands.remove_resource::<TransitionTable>();
+                        for entity in &table_ui{
+                            commands.entity(entity).despawn();
+                        }
+                        sound = Some(GameSoundType::Select);
+                    }
+                },
+            }
+        },
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume: settings.volume(Bus::TapeEffects), ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///a cell of the grid, sized so the visible columns and the state names fill the panel
+fn spawn_cell(parent: &mut ChildSpawnerCommands, text: String, background: Color, text_color: Color, outline: Option<Color>){
+    parent.spawn((
+        Node{
+            width: Val::Percent(100.0 / (VISIBLE_COLUMNS + 1) as f32 - CELL_GAP_PER),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            overflow: Overflow::clip(),
+            ..Default::default()
+        },
+        BackgroundColor(background),
+        Outline::new(Val::Px(NEXT_OUTLINE_PX), Val::ZERO, outline.unwrap_or(Color::NONE)),
+    )).with_child((
+        Text::new(text),
+        TextFont{
+            font_size: CELL_FONT_SIZE,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(CELL_FONT_SIZE),
+        TextColor(text_color),
+        TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+    ));
+}
+
+///draws the visible part of the table again whenever it or the machine changes
+pub fn update_table(
+    mut commands: Commands,
+    table: Res<TransitionTable>,
+    sim: Res<Sim>,
+    table_ui: Query<Entity, With<TableUI>>,
+){
+    if !table.is_changed() && !sim.is_changed(){
+        return;
+    }
+    for entity in &table_ui{
+        commands.entity(entity).despawn();
+    }
+
+    let machine = &sim.machine;
+    let rows = rows(machine);
+    let columns = columns(machine);
+    //the cell the machine will take, unless it has halted
+    let read = sim.read();
+    let next = (!machine.choices(&sim.state, &read).is_empty()).then_some((sim.state.as_str(), read.as_slice()));
+    let (top, left) = table.scroll;
+    let visible_columns = &columns[left.min(columns.len())..(left + VISIBLE_COLUMNS).min(columns.len())];
+    let hint = if table.editing.is_some() {EDIT_HINT} else {BROWSE_HINT};
+    let error = table.error.clone().unwrap_or_default();
+
+    spawn_panel(&mut commands, TableUI, Placement::Centered{width: PANEL_WIDTH_PER, height: PANEL_HEIGHT_PER}, Some(TITLE_TEXT), |panel| {
+        panel.spawn(Node{
+            width: Val::Percent(100.0),
+            flex_grow: 1.0,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Percent(CELL_GAP_PER),
+            ..Default::default()
+        }).with_children(|grid| {
+            let row_height = 100.0 / (VISIBLE_ROWS + 1) as f32 - CELL_GAP_PER;
+            let row_node = || Node{
+                width: Val::Percent(100.0),
+                height: Val::Percent(row_height),
+                column_gap: Val::Percent(CELL_GAP_PER),
+                ..Default::default()
+            };
+            grid.spawn(row_node()).with_children(|header| {
+                spawn_cell(header, String::new(), HEADER_COLOR, TEXT_COLOR, None);
+                for read in visible_columns{
+                    spawn_cell(header, read.iter().collect(), HEADER_COLOR, TEXT_COLOR, None);
+                }
+            });
+            for (i, &state) in rows.iter().enumerate().skip(top).take(VISIBLE_ROWS){
+                grid.spawn(row_node()).with_children(|row| {
+                    spawn_cell(row, state.to_string(), HEADER_COLOR, TEXT_COLOR, None);
+                    for (j, read) in visible_columns.iter().enumerate(){
+                        let selected = i == table.row && left + j == table.column;
+                        let text = match &table.editing{
+                            Some(text) if selected => format!("{}{}", text, CARET),
+                            _ => cell_text(machine.choices(state, read)),
+                        };
+                        let (background, text_color) = if selected {(SELECTED_COLOR, SELECTED_TEXT_COLOR)} else {(CELL_COLOR, TEXT_COLOR)};
+                        let outline = (next == Some((state, read.as_slice()))).then_some(NEXT_COLOR);
+                        spawn_cell(row, text, background, text_color, outline);
+                    }
+                });
+            }
+        });
+        panel.spawn(panel_text(error, ERROR_FONT_SIZE)).insert(TextColor(ERROR_COLOR));
+    }, Some(hint));
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use bevy::{audio::PlaybackMode, prelude::*, text::FontSmoothing};
+use turing_core::{ComputationTree, Outcome, Status};
+
+use crate::{actions::{Action, Actions}, gamepad, games::{panel_text, run::RunState, spawn_panel, GameSoundType, GameSounds, Placement, Sim}, paths::AppPaths, settings::{Bus, Settings}, toast::{show_toast, Toast}, BaseFontSize};
+
+//panel
+const PANEL_WIDTH_PER: f32 = 80.0;
+const PANEL_HEIGHT_PER: f32 = 80.0;
+//text
+const TITLE_TEXT: &str = "Computation Tree";
+const SUMMARY_FONT_SIZE: f32 = 24.0;
+const TREE_FONT_SIZE: f32 = 18.0;
+///branches listed before the rest are left out
+const MAX_TREE_LINES: usize = 20;
+const INDENT: &str = "  ";
+///marks the branches leading to the accepting configuration
+const PATH_MARKER: &str = "* ";
+//limit
+const MIN_EXPLORATION_LIMIT: usize = 100;
+const MAX_EXPLORATION_LIMIT: usize = 50_000;
+
+///the configurations reachable from the current one, shown while this exists
+#[derive(Resource)]
+pub struct TreeView{
+    tree: ComputationTree,
+    ///configurations explored before giving up
+    limit: usize,
+}
+
+///marker for the tree view's ui
+#[derive(Component)]
+pub struct TreeUI;
+
+///opens the tree view, changes how far it explores, and follows the accepting branch
+#[allow(clippy::too_many_arguments)]
+pub fn view_controls(
+    mut commands: Commands,
+    view: Option<ResMut<TreeView>>,
+    actions: Actions,
+    gamepads: Query<&Gamepad>,
+    mut sim: ResMut<Sim>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+    tree_ui: Query<Entity, With<TreeUI>>,
+    sounds: Res<GameSounds>,
+    settings: Res<Settings>,
+){
+    let volume = settings.volume(Bus::TapeEffects);
+    let mut sound = None;
+    match view{
+        None => {
+            if actions.just_pressed(Action::ExploreTree){
+                next_run_state.set(RunState::Paused);
+                let limit = settings.exploration_limit.clamp(MIN_EXPLORATION_LIMIT, MAX_EXPLORATION_LIMIT);
+                let view = TreeView{tree: ComputationTree::explore(&sim, limit), limit};
+                spawn_view(&mut commands, &view);
+                commands.insert_resource(view);
+                sound = Some(GameSoundType::Select);
+            }
+        },
+        Some(mut view) => {
+            let mut new_limit = None;
+            if actions.just_pressed(Action::SpeedUp){
+                new_limit = Some((view.limit * 2).min(MAX_EXPLORATION_LIMIT));
+            }
+            if actions.just_pressed(Action::SpeedDown){
+                new_limit = Some((view.limit / 2).max(MIN_EXPLORATION_LIMIT));
+            }
+            if let Some(limit) = new_limit{
+                if limit == view.limit{
+                    sound = Some(GameSoundType::CantMove);
+                }else{
+                    view.limit = limit;
+                    view.tree = ComputationTree::explore(&sim, limit);
+                    for entity in &tree_ui{
+                        commands.entity(entity).despawn();
+                    }
+                    spawn_view(&mut commands, &view);
+                    sound = Some(GameSoundType::Move);
+                }
+            }
+
+            let mut followed = false;
+            if actions.just_pressed(Action::Step) || gamepad::just_pressed(&gamepads, GamepadButton::South){
+                match view.tree.outcome{
+                    Outcome::Accepted(node) => {
+                        for choice in view.tree.path(node){
+                            sim.step_choice(choice);
+                        }
+                        followed = true;
+                        sound = Some(GameSoundType::Select);
+                    },
+                    _ => sound = Some(GameSoundType::CantMove),
+                }
+            }
+            let close = actions.just_pressed(Action::Exit) || actions.just_pressed(Action::ExploreTree) || gamepad::just_pressed(&gamepads, GamepadButton::East);
+            if followed || close{
+                commands.remove_resource::<TreeView>();
+                for entity in &tree_ui{
+                    commands.entity(entity).despawn();
+                }
+            }
+        },
+    }
+
+    if let Some(sound) = sound{
+        commands.spawn((AudioPlayer::new(sounds[&sound].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::TapeEffects));
+    }
+}
+
+///keeps the exploration limit chosen in the tree view for next time
+pub fn store_limit(
+    mut commands: Commands,
+    view: Res<TreeView>,
+    mut settings: ResMut<Settings>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+){
+    if settings.exploration_limit == view.limit{
+        return;
+    }
+    settings.exploration_limit = view.limit;
+    if let Err(e) = settings.save(&paths){
+        show_toast(&mut commands, &toasts, format!("Couldn't save settings: {}", e));
+    }
+}
+
+fn summary(view: &TreeView) -> String{
+    let explored = view.tree.nodes.len();
+    match view.tree.outcome{
+        Outcome::Accepted(node) => format!("Accepts after {} steps ({} configurations explored)", view.tree.nodes[node].depth, explored),
+        Outcome::Rejected => format!("Every branch halts without accepting ({} configurations explored)", explored),
+        Outcome::Undecided => format!("No branch accepts within {} configurations", explored),
+    }
+}
+
+///one line per branch, running from where it splits off to where it splits again or ends
+fn tree_lines(tree: &ComputationTree) -> Vec<String>{
+    let mut on_path = HashSet::new();
+    if let Outcome::Accepted(mut node) = tree.outcome{
+        on_path.insert(node);
+        while let Some(parent) = tree.nodes[node].parent{
+            on_path.insert(parent);
+            node = parent;
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut branches = vec![(0, 0)];
+    while let Some((start, depth)) = branches.pop(){
+        if lines.len() == MAX_TREE_LINES{
+            lines.push("...".to_string());
+            break;
+        }
+        let mut end = start;
+        while let [only_child] = tree.nodes[end].children[..]{
+            end = only_child;
+        }
+        let end_node = &tree.nodes[end];
+        let ending = match end_node.status{
+            Status::Accepted => "accepts".to_string(),
+            Status::Rejected => "rejects".to_string(),
+            Status::Stuck => "gets stuck".to_string(),
+            Status::Running if end_node.children.is_empty() => "not explored further".to_string(),
+            Status::Running => format!("splits {} ways", end_node.children.len()),
+        };
+        let name = if start == 0 {"start".to_string()} else {format!("choice {}", tree.nodes[start].choice + 1)};
+        let marker = if on_path.contains(&end) {PATH_MARKER} else {""};
+        lines.push(format!("{}{}{}: {} at step {}, {}", INDENT.repeat(depth), marker, name, end_node.state, end_node.depth, ending));
+        //reversed so the first choice is listed first
+        for &child in end_node.children.iter().rev(){
+            branches.push((child, depth + 1));
+        }
+    }
+    lines
+}
+
+fn spawn_view(
+    commands: &mut Commands,
+    view: &TreeView,
+){
+    let hint = format!("Step: follow the accepting branch   Speed Up/Down: explore {} configurations   Exit: close", view.limit);
+    spawn_panel(commands, TreeUI, Placement::Centered{width: PANEL_WIDTH_PER, height: PANEL_HEIGHT_PER}, Some(TITLE_TEXT), |panel| {
+        panel.spawn(panel_text(summary(view), SUMMARY_FONT_SIZE));
+        panel.spawn((
+            Node{
+                width: Val::Percent(100.0),
+                flex_grow: 1.0,
+                ..Default::default()
+            },
+            Text::new(tree_lines(&view.tree).join("\n")),
+            TextFont{
+                font_size: TREE_FONT_SIZE,
+                font_smoothing: FontSmoothing::AntiAliased,
+                ..Default::default()
+            },
+            BaseFontSize(TREE_FONT_SIZE),
+            TextColor(Color::WHITE),
+            TextLayout::new_with_justify(JustifyText::Left).with_no_wrap(),
+        ));
+    }, Some(&hint));
+}
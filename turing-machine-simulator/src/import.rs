@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+use bevy::prelude::*;
+use turing_core::{diagram, jflap, morphett, save, slots};
+
+use crate::{paths::AppPaths, toast::{show_toast, Toast}};
+
+const JFLAP_EXTENSION: &str = "jff";
+const DOT_EXTENSION: &str = "dot";
+const SVG_EXTENSION: &str = "svg";
+
+///what happened when a machine from another simulator was imported
+#[derive(Resource, Debug)]
+pub struct ImportReport{
+    ///slot the machine was put in
+    pub slot: String,
+    ///parts of the file that were changed or left out
+    pub warnings: Vec<String>,
+}
+
+///whether `path` is a JFLAP file rather than one for turingmachinesimulator.com
+fn is_jflap(path: &Path) -> bool{
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(JFLAP_EXTENSION))
+}
+
+///converts the machine at `path` and puts it in a new save slot.
+///
+///`.jff` files are read as JFLAP machines and anything else as a turingmachinesimulator.com machine
+pub fn import_file(path: &Path, paths: &AppPaths) -> Result<ImportReport, String>{
+    let text = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let import = if is_jflap(path) {jflap::import(&text)} else {morphett::import(&text)};
+    let import = import.map_err(|e| format!("{}:{}", path.display(), e))?;
+    let title = import.name.unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+    let slot = slots::create_from(&paths.saves_dir(), &title, &import.description.into_simulation())
+        .map_err(|e| e.to_string())?;
+    Ok(ImportReport{
+        slot,
+        warnings: import.warnings.iter().map(|warning| format!("{}:{}", path.display(), warning)).collect(),
+    })
+}
+
+///writes the machine of the save called `slot` to `path`, giving back what won't read back the same.
+///
+///`.jff` files get a JFLAP machine, `.dot` a Graphviz state diagram and `.svg` a drawn one
+pub fn export_file(slot: &str, path: &Path, paths: &AppPaths) -> Result<Vec<String>, String>{
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    if !matches!(extension.as_deref(), Some(JFLAP_EXTENSION | DOT_EXTENSION | SVG_EXTENSION)){
+        return Err(format!("{} needs to end in .{}, .{} or .{}, the formats machines can be exported to", path.display(), JFLAP_EXTENSION, DOT_EXTENSION, SVG_EXTENSION));
+    }
+    let (simulation, _) = save::read_world(&slots::slot_path(&paths.saves_dir(), slot)).map_err(|e| e.to_string())?;
+    let (text, warnings) = match extension.as_deref(){
+        Some(DOT_EXTENSION) => (diagram::dot(&simulation.machine, slot), Vec::new()),
+        Some(SVG_EXTENSION) => (diagram::svg(&simulation.machine), Vec::new()),
+        _ => {
+            let export = jflap::export(&simulation.machine).map_err(|e| format!("{} can't be exported to JFLAP: {}", slot, e))?;
+            (export.xml, export.warnings)
+        },
+    };
+    fs::write(path, text).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    Ok(warnings)
+}
+
+///tells the player where the import went and whether anything was lost
+pub fn show_report(
+    mut commands: Commands,
+    report: Res<ImportReport>,
+    toasts: Query<Entity, With<Toast>>,
+){
+    let message = match &report.warnings[..]{
+        [] => format!("Imported into {}", report.slot),
+        [warning] => format!("Imported into {}: {}", report.slot, warning),
+        [first, rest @ ..] => format!("Imported into {}: {} (and {} more)", report.slot, first, rest.len()),
+    };
+    show_toast(&mut commands, &toasts, message);
+}
@@ -0,0 +1,78 @@
+use std::path::{self, Path, PathBuf};
+use bevy::{asset::{io::{AssetSourceBuilder, AssetSourceId, Reader}, AssetLoader, AssetPath, LoadContext}, prelude::*};
+use turing_core::Description;
+
+use crate::paths::AppPaths;
+
+///asset source reading from the saves directory
+pub const SAVES_SOURCE: &str = "saves";
+///asset source reading from the folder of the machine given on the command line
+pub const MACHINE_SOURCE: &str = "machine";
+const MACHINE_EXTENSIONS: [&str; 1] = ["tm"];
+
+///a parsed machine description file
+#[derive(Asset, TypePath, Deref)]
+pub struct MachineAsset(pub Description);
+
+///parses `.tm` files into [`MachineAsset`]s
+#[derive(Default)]
+pub struct MachineLoader;
+
+impl AssetLoader for MachineLoader{
+    type Asset = MachineAsset;
+    type Settings = ();
+    type Error = String;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error>{
+        let path = load_context.path().display().to_string();
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(|e| format!("could not read {}: {}", path, e))?;
+        let text = String::from_utf8(bytes).map_err(|_| format!("{} is not valid text", path))?;
+        Description::parse(&text).map(MachineAsset).map_err(|e| e.to_string())
+    }
+
+    fn extensions(&self) -> &[&str]{
+        &MACHINE_EXTENSIONS
+    }
+}
+
+pub struct MachineAssetPlugin;
+
+impl Plugin for MachineAssetPlugin{
+    fn build(&self, app: &mut App){
+        app
+        .init_asset::<MachineAsset>()
+        .init_asset_loader::<MachineLoader>();
+    }
+}
+
+///adds asset sources for the saves directory and the folder of `machine`, so their machines can be watched for changes.
+///has to happen before the asset plugin is added
+pub fn register_sources(app: &mut App, paths: &AppPaths, machine: Option<&Path>){
+    let folders = [
+        (SAVES_SOURCE, Some(paths.saves_dir())),
+        (MACHINE_SOURCE, machine.and_then(machine_folder)),
+    ];
+    for (source, folder) in folders{
+        //relative folders would be read from next to the executable rather than the working directory
+        let Some(folder) = folder.and_then(|folder| path::absolute(folder).ok()) else{
+            continue;
+        };
+        app.register_asset_source(AssetSourceId::from(source), AssetSourceBuilder::platform_default(&folder.to_string_lossy(), None));
+    }
+}
+
+///folder holding the machine file at `path`
+fn machine_folder(path: &Path) -> Option<PathBuf>{
+    Some(path::absolute(path).ok()?.parent()?.to_path_buf())
+}
+
+///asset path of the file called `file_name` in `source`
+pub fn asset_path(source: &'static str, file_name: &Path) -> AssetPath<'static>{
+    AssetPath::from_path(file_name).with_source(source).into_owned()
+}
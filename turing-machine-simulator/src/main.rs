@@ -1,34 +1,75 @@
 #![allow(dead_code)]
 #![cfg_attr(all(target_os = "windows", not(debug_assertions)), windows_subsystem = "windows")]
-use bevy::{audio::Volume, prelude::*, window::{WindowResized, WindowResolution}};
+use bevy::{prelude::*, window::{WindowResized, WindowResolution}};
 
 use crate::menus::MenuState;
 
 mod menus;
 mod games;
+mod toast;
+mod cli;
+mod paths;
+mod assets;
+mod settings;
+mod gamepad;
+mod actions;
+mod machine_asset;
+mod import;
 
 const BASE_WINDOW_HEIGHT: f32 = 800.0;
 const BASE_WINDOW_WIDTH: f32 = 1200.0;
 const BASE_WINDOW_ASPECT_RATIO: f32 = BASE_WINDOW_WIDTH / BASE_WINDOW_HEIGHT;
-const AUDIO_FILE_PREFIX: &'static str = "audio\\";
 
 /// controls the current app state
 #[derive(States, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum AppState{
     InGame,
     InMenu,
-    Paused,
     Transition,
 }
 
 #[derive(Component, Deref, DerefMut)]
 pub struct BaseFontSize(f32);
 
-#[derive(Resource, Deref, DerefMut)]
-pub struct CurVolume(Volume);
-
 fn main() {
-    App::new()
+    let args = cli::Args::parse();
+    let paths = paths::AppPaths::resolve(&args);
+    if let Err(e) = paths.migrate_legacy_saves(){
+        eprintln!("failed to copy old saves to {}: {}", paths.saves_dir().display(), e);
+    }
+    let settings = settings::Settings::load(&paths).unwrap_or_else(|e| {
+        eprintln!("using default settings: {}", e);
+        settings::Settings::default()
+    });
+
+    if let Some((slot, path)) = &args.export{
+        match import::export_file(slot, path, &paths){
+            Ok(warnings) => for warning in warnings{
+                eprintln!("{}: {}", path.display(), warning);
+            },
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
+    let report = args.import.as_ref().map(|path| import::import_file(path, &paths).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }));
+    for warning in report.iter().flat_map(|report| &report.warnings){
+        eprintln!("{}", warning);
+    }
+
+    let imported_slot = report.as_ref().map(|report| report.slot.clone());
+
+    let mut app = App::new();
+    machine_asset::register_sources(&mut app, &paths, args.machine.as_deref());
+    if let Some(report) = report{
+        app.insert_resource(report);
+    }
+    app
     .add_plugins(DefaultPlugins.set(WindowPlugin{
         primary_window: Some(Window{
             title: "Turing Machine Simulator!".to_string(),
@@ -38,13 +79,23 @@ fn main() {
         }),
         ..Default::default()
     }))
+    .add_plugins(assets::AssetManifestPlugin)
+    .add_plugins(machine_asset::MachineAssetPlugin)
+    .add_plugins(gamepad::GamepadNavigationPlugin)
     .add_plugins(menus::MenuPlugin)
     .add_plugins(games::GamePlugin)
-    .insert_resource(CurVolume(Volume::Linear(1.0)))
+    .add_plugins(toast::ToastPlugin)
+    .insert_resource(settings)
+    .insert_resource(paths)
+    .insert_resource(games::MachineFile(args.machine))
+    .insert_resource(games::SaveSlot(imported_slot))
     .insert_state(AppState::InMenu)
     .add_systems(
         Startup,
-        spawn_camera
+        (
+            spawn_camera,
+            import::show_report.run_if(resource_exists::<import::ImportReport>),
+        )
     )
     .add_systems(
         OnEnter(AppState::Transition),
@@ -52,7 +103,10 @@ fn main() {
     )
     .add_systems(
         Update,
-        scale_text
+        (
+            scale_text,
+            settings::apply_volumes.run_if(resource_changed::<settings::Settings>),
+        )
     )
     
     .run();
@@ -61,7 +115,7 @@ fn main() {
 fn spawn_camera(
     mut commands: Commands,
 ){
-    commands.spawn(Camera2d::default());
+    commands.spawn(Camera2d);
 }
 
 fn transition(
@@ -1,5 +1,7 @@
-use bevy::{audio::PlaybackMode, prelude::*};
-use crate::{games::{GameState, SaveFileIndex}, AppState, CurVolume, AUDIO_FILE_PREFIX};
+use bevy::{audio::PlaybackMode, input::mouse::MouseWheel, prelude::*};
+use crate::{assets::{AssetRegistry, MENU_SOUND_FILES}, gamepad, games::{GameState, SaveSlot}, paths::AppPaths, settings::{Bus, Settings}, toast::Toast, AppState};
+use controls_menu::Rebinding;
+use sandbox_menu::{RenameBuffer, SaveList};
 use std::collections::HashMap;
 use std::slice::Iter;
 
@@ -8,7 +10,6 @@ const BUTTON_SELECTED_COLOR: Color = Color::linear_rgb(1.0, 1.0, 1.0);
 const BUTTON_OUTLINE_UNSELECTED_WIDTH_PER: f32 = 0.5;
 const BUTTON_OUTLINE_SELECTED_WIDTH_PER: f32 = 0.75;
 
-const AUDIO_FILES: [&'static str; 3] = ["menu-move.mp3", "menu-select.mp3", "menu-back.mp3"];
 ///types of menu sound effects.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum MenuSoundType{
@@ -19,7 +20,7 @@ enum MenuSoundType{
 
 impl MenuSoundType{
     pub fn iterator() -> Iter<'static, MenuSoundType>{
-        static MENU_SOUND_TYPES: [MenuSoundType; AUDIO_FILES.len()] = [MenuSoundType::Move, MenuSoundType::Select, MenuSoundType::Back];
+        static MENU_SOUND_TYPES: [MenuSoundType; MENU_SOUND_FILES.len()] = [MenuSoundType::Move, MenuSoundType::Select, MenuSoundType::Back];
         MENU_SOUND_TYPES.iter()
     }
 }
@@ -46,6 +47,19 @@ pub struct ButtonCount(usize);
 pub enum TransitionType{
     In,
     Out,
+    ///stays in the current menu without reloading it
+    Stay,
+}
+
+///a navigation request from the keyboard, mouse or a gamepad
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuInput{
+    Previous,
+    Next,
+    ///selects a button directly, like hovering over it
+    Point(usize),
+    Confirm,
+    Back,
 }
 
 mod main_menu;
@@ -53,6 +67,7 @@ mod settings_menu;
 mod credits_menu;
 mod game_menu;
 mod sandbox_menu;
+mod controls_menu;
 
 /// controls the current menu
 #[derive(States, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -61,6 +76,7 @@ pub enum MenuState{
     GameMenu,
     SandboxMenu,
     SettingsMenu,
+    ControlsMenu,
     CreditsMenu,
     QuitMenu,
     None,
@@ -74,6 +90,9 @@ impl Plugin for MenuPlugin{
     .insert_state(MenuState::MainMenu)
     .insert_resource(PlayerIndex::default())
     .insert_resource(ButtonCount::default())
+    .insert_resource(SaveList::default())
+    .insert_resource(settings_menu::DraggedSlider::default())
+    .add_event::<MenuInput>()
     .add_systems(
         Startup,
         load_audio,
@@ -89,27 +108,91 @@ impl Plugin for MenuPlugin{
     .add_systems(
     Update,
     (
-        controls.run_if(in_state(AppState::InMenu)),
+        (
+            keyboard_input,
+            mouse_input,
+            gamepad_input,
+            controls,
+        ).chain().run_if(in_state(AppState::InMenu)).run_if(not(resource_exists::<RenameBuffer>)).run_if(not(resource_exists::<Rebinding>)),
+        controls_menu::capture_key.run_if(resource_exists::<Rebinding>),
+        controls_menu::update_bindings.run_if(in_state(MenuState::ControlsMenu)).run_if(in_state(AppState::InMenu)),
+        sandbox_menu::save_controls.run_if(in_state(MenuState::SandboxMenu)).run_if(in_state(AppState::InMenu)),
+        sandbox_menu::scroll_to_selection.run_if(in_state(MenuState::SandboxMenu)).run_if(in_state(AppState::InMenu)),
+        sandbox_menu::update_save_rows.run_if(in_state(MenuState::SandboxMenu)).run_if(in_state(AppState::InMenu)),
         button_selection.run_if(in_state(AppState::InMenu)),
         settings_menu::update_sliders.run_if(in_state(MenuState::SettingsMenu)),
         settings_menu::slider_controls.run_if(in_state(MenuState::SettingsMenu)),
+        settings_menu::drag_sliders.run_if(in_state(MenuState::SettingsMenu)),
     ).chain());
     }
 }
 
 fn load_audio(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    registry: Res<AssetRegistry>,
 ){
     let mut sounds = HashMap::new();
-    for (&menu_sound_type, &file_name) in MenuSoundType::iterator().zip(AUDIO_FILES.iter()){
-        let path = AUDIO_FILE_PREFIX.to_owned() + file_name;
-        sounds.insert(menu_sound_type, asset_server.load(path));
+    for (&menu_sound_type, &file_name) in MenuSoundType::iterator().zip(MENU_SOUND_FILES.iter()){
+        sounds.insert(menu_sound_type, registry.sound(file_name));
     }
 
     commands.insert_resource(MenuSounds(sounds));
 }
 
+///turns key presses into menu inputs
+fn keyboard_input(
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut menu_inputs: EventWriter<MenuInput>,
+){
+    for (key, input) in [(KeyCode::ArrowUp, MenuInput::Previous), (KeyCode::ArrowDown, MenuInput::Next), (KeyCode::Enter, MenuInput::Confirm), (KeyCode::Escape, MenuInput::Back)]{
+        if inputs.just_pressed(key){
+            menu_inputs.write(input);
+        }
+    }
+}
+
+///turns d-pad and face button presses into menu inputs
+fn gamepad_input(
+    gamepads: Query<&Gamepad>,
+    mut menu_inputs: EventWriter<MenuInput>,
+){
+    for (button, input) in [(GamepadButton::DPadUp, MenuInput::Previous), (GamepadButton::DPadDown, MenuInput::Next), (GamepadButton::South, MenuInput::Confirm), (GamepadButton::East, MenuInput::Back)]{
+        if gamepad::just_pressed(&gamepads, button){
+            menu_inputs.write(input);
+        }
+    }
+}
+
+///hovering a button selects it, clicking it activates it and the wheel scrolls through them
+#[allow(clippy::type_complexity)]
+fn mouse_input(
+    buttons: Query<(&ButtonIndex, &Interaction), (Changed<Interaction>, With<Button>)>,
+    mut cursor_moves: EventReader<CursorMoved>,
+    mut wheel: EventReader<MouseWheel>,
+    mut menu_inputs: EventWriter<MenuInput>,
+){
+    //buttons respawned under a still cursor shouldn't steal the selection from the keyboard
+    let cursor_moved = cursor_moves.read().count() > 0;
+    for (index, interaction) in &buttons{
+        match interaction{
+            Interaction::Hovered if cursor_moved => {
+                menu_inputs.write(MenuInput::Point(**index));
+            },
+            Interaction::Pressed => {
+                menu_inputs.write_batch([MenuInput::Point(**index), MenuInput::Confirm]);
+            },
+            _ => (),
+        }
+    }
+    for scroll in wheel.read(){
+        if scroll.y > 0.0{
+            menu_inputs.write(MenuInput::Previous);
+        }else if scroll.y < 0.0{
+            menu_inputs.write(MenuInput::Next);
+        }
+    }
+}
+
 /// sets a button's background color and border width depending off it is selected or not
 fn button_selection(
     player_index: Res<PlayerIndex>,
@@ -128,10 +211,11 @@ fn button_selection(
 }
 
 /// handles controls while in the menu
+#[allow(clippy::too_many_arguments)]
 fn controls(
     mut player_index: ResMut<PlayerIndex>,
-    save_file_index: ResMut<SaveFileIndex>, 
-    inputs: Res<ButtonInput<KeyCode>>,
+    save_slot: ResMut<SaveSlot>, 
+    mut menu_inputs: EventReader<MenuInput>,
     exit: EventWriter<AppExit>,
     menu_state: Res<State<MenuState>>,
     next_menu_state: ResMut<NextState<MenuState>>,
@@ -140,40 +224,64 @@ fn controls(
     button_count: Res<ButtonCount>,
     mut commands: Commands,
     sounds: Res<MenuSounds>,
-    volume: Res<CurVolume>,
+    settings: ResMut<Settings>,
+    save_list: Res<SaveList>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
 ){
-    let volume = volume.0;
-    if inputs.just_pressed(KeyCode::ArrowUp){
-        **player_index = player_index.checked_sub(1).unwrap_or(**button_count - 1);
-        commands.spawn((AudioPlayer(sounds[&MenuSoundType::Move].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
-    }else if inputs.just_pressed(KeyCode::ArrowDown){
-        **player_index = (**player_index + 1) % **button_count;
-        commands.spawn((AudioPlayer(sounds[&MenuSoundType::Move].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
+    let volume = settings.volume(Bus::MenuEffects);
+    let (mut confirm, mut back) = (false, false);
+    for &input in menu_inputs.read(){
+        let moved = match input{
+            MenuInput::Previous => {
+                **player_index = player_index.checked_sub(1).unwrap_or(**button_count - 1);
+                true
+            },
+            MenuInput::Next => {
+                **player_index = (**player_index + 1) % **button_count;
+                true
+            },
+            MenuInput::Point(index) if index != **player_index && index < **button_count => {
+                **player_index = index;
+                true
+            },
+            MenuInput::Point(_) => false,
+            MenuInput::Confirm => {confirm = true; false},
+            MenuInput::Back => {back = true; false},
+        };
+        if moved{
+            commands.spawn((AudioPlayer(sounds[&MenuSoundType::Move].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::MenuEffects));
+        }
     }
     **player_index = player_index.clamp(0, **button_count - 1);
 
-    if inputs.just_pressed(KeyCode::Enter){
-        next_app_state.set(AppState::Transition);
-        match match **menu_state{
+    if confirm{
+        let transition = match **menu_state{
             MenuState::MainMenu => main_menu::transition(player_index, exit, next_menu_state),
             MenuState::GameMenu => game_menu::transition(player_index, next_menu_state, next_game_state),
             MenuState::CreditsMenu => credits_menu::transition(next_menu_state),
-            MenuState::SandboxMenu => sandbox_menu::transition(player_index, save_file_index, next_menu_state, next_game_state),
+            MenuState::SandboxMenu => sandbox_menu::transition(player_index, save_slot, save_list, next_menu_state, next_game_state, commands.reborrow(), toasts, paths),
             MenuState::SettingsMenu => settings_menu::transition(player_index, next_menu_state),
+            MenuState::ControlsMenu => controls_menu::transition(player_index, next_menu_state, commands.reborrow(), settings, toasts, paths),
             _ => panic!("unimplemented menu"),
-        }{
-            TransitionType::In => commands.spawn((AudioPlayer(sounds[&MenuSoundType::Select].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()})),
-            TransitionType::Out => commands.spawn((AudioPlayer(sounds[&MenuSoundType::Back].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()})),
         };
-    }else if inputs.just_pressed(KeyCode::Escape){
+        if !matches!(transition, TransitionType::Stay){
+            next_app_state.set(AppState::Transition);
+        }
+        match transition{
+            TransitionType::In | TransitionType::Stay => commands.spawn((AudioPlayer(sounds[&MenuSoundType::Select].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::MenuEffects)),
+            TransitionType::Out => commands.spawn((AudioPlayer(sounds[&MenuSoundType::Back].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::MenuEffects)),
+        };
+    }else if back{
         next_app_state.set(AppState::Transition);
-        commands.spawn((AudioPlayer(sounds[&MenuSoundType::Back].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}));
+        commands.spawn((AudioPlayer(sounds[&MenuSoundType::Back].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::MenuEffects));
         match **menu_state{
             MenuState::MainMenu => main_menu::detransition(exit),
             MenuState::GameMenu => game_menu::detransition(next_menu_state),
             MenuState::CreditsMenu => credits_menu::detransition(next_menu_state),
             MenuState::SandboxMenu => sandbox_menu::detransition(next_menu_state),
             MenuState::SettingsMenu => settings_menu::detransition(next_menu_state),
+            MenuState::ControlsMenu => controls_menu::detransition(next_menu_state),
             _ => panic!("unimplemented menu"),
         }
     }
@@ -190,6 +298,7 @@ fn unload_ui(
 }
 
 /// loads all menu ui elements
+#[allow(clippy::too_many_arguments)]
 fn load_ui(
     commands: Commands,
     button_count: ResMut<ButtonCount>,
@@ -197,15 +306,19 @@ fn load_ui(
     mut player_index: ResMut<PlayerIndex>,
     meshes: ResMut<Assets<Mesh>>,
     mats: ResMut<Assets<ColorMaterial>>,
-    volume: Res<CurVolume>,
+    settings: Res<Settings>,
+    save_list: ResMut<SaveList>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
 ){
     **player_index = 0;
     match **menu_state{
         MenuState::MainMenu => main_menu::load(commands, button_count),
         MenuState::GameMenu => game_menu::load(commands, button_count), 
         MenuState::CreditsMenu => credits_menu::load(commands, button_count),
-        MenuState::SandboxMenu => sandbox_menu::load(commands, button_count),
-        MenuState::SettingsMenu => settings_menu::load(commands, button_count, meshes, mats, volume),
+        MenuState::SandboxMenu => sandbox_menu::load(commands, button_count, save_list, toasts, paths),
+        MenuState::SettingsMenu => settings_menu::load(commands, button_count, meshes, mats, settings),
+        MenuState::ControlsMenu => controls_menu::load(commands, button_count, settings),
         _ => print!("unimplemented menu"),
     }
 }
\ No newline at end of file
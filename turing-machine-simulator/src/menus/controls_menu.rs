@@ -0,0 +1,258 @@
+use bevy::{audio::PlaybackMode, prelude::*};
+use bevy::text::FontSmoothing;
+
+use crate::actions::{Action, KeyBinding};
+use crate::paths::AppPaths;
+use crate::settings::{Bus, Settings};
+use crate::toast::{show_toast, Toast};
+use crate::{menus::{ButtonCount, ButtonIndex, MenuSoundType, MenuSounds, MenuUI, PlayerIndex, TransitionType, BUTTON_OUTLINE_UNSELECTED_WIDTH_PER, BUTTON_UNSELECTED_COLOR}, BaseFontSize, MenuState};
+//title
+const TITLE_HEIGHT_PER: f32 = 12.0;
+const TITLE_FONT_SIZE: f32 = 70.0;
+//hint
+const HINT_TOP_PER: f32 = 11.0;
+const HINT_FONT_SIZE: f32 = 20.0;
+const HINT_TEXT: &str = "Enter: rebind, then press the new key (Escape cancels)";
+const HINT_COLOR: Color = Color::WHITE;
+//binding rows
+const ROW_WIDTH_PER: f32 = 70.0;
+const ROW_HEIGHT_PER: f32 = 3.5;
+const ROW_SPACING_PER: f32 = 1.0;
+const ROW_TOP_PER: f32 = 16.0;
+const ROW_FONT_SIZE: f32 = 24.0;
+//buttons
+const BUTTON_WIDTH_PER: f32 = 38.0;
+const BUTTON_HEIGHT_PER: f32 = 10.0;
+const BUTTON_OUTLINE_COLOR: Color = Color::BLACK;
+const BUTTON_SPACING_PER: f32 = 4.0;
+const BUTTON_BOTTOM_PER: f32 = 3.0;
+//button text
+const BUTTON_TEXT: [&str; 2] = ["Reset to Defaults", "Back"];
+const BUTTON_TEXT_COLOR: Color = Color::BLACK;
+const BUTTON_TEXT_FONT_SIZE: f32 = 40.0;
+
+///action waiting for its new key
+#[derive(Resource)]
+pub struct Rebinding{
+    action: Action,
+    ///false on the frame the rebind started, so the key that started it isn't taken as the new key
+    armed: bool,
+}
+
+///text showing an action and its key
+#[derive(Component, Deref)]
+pub struct BindingLabel(Action);
+
+pub fn load(
+    mut commands: Commands,
+    mut button_count: ResMut<ButtonCount>,
+    settings: Res<Settings>,
+){
+    **button_count = Action::ALL.len() + BUTTON_TEXT.len();
+    //title text
+    commands.spawn((
+        MenuUI,
+        Node{
+            width: Val::Percent(100.0),
+            height: Val::Percent(TITLE_HEIGHT_PER),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        BackgroundColor(Color::NONE),
+    )).with_child((
+        Text::new("Controls"),
+        TextFont{
+            font_size: TITLE_FONT_SIZE,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(TITLE_FONT_SIZE),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+    ));
+    //controls hint
+    commands.spawn((
+        MenuUI,
+        Node{
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Percent(HINT_TOP_PER),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+    )).with_child((
+        Text::new(HINT_TEXT),
+        TextFont{
+            font_size: HINT_FONT_SIZE,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(HINT_FONT_SIZE),
+        TextColor(HINT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+    ));
+    //one row per action
+    for (i, &action) in Action::ALL.iter().enumerate(){
+        commands.spawn((
+            MenuUI,
+            Button,
+            ButtonIndex(i),
+            Node{
+                position_type: PositionType::Absolute,
+                width: Val::Percent(ROW_WIDTH_PER),
+                height: Val::Percent(ROW_HEIGHT_PER),
+                top: Val::Percent(ROW_TOP_PER + (ROW_HEIGHT_PER + ROW_SPACING_PER) * i as f32),
+                left: Val::Percent((100.0 - ROW_WIDTH_PER) / 2.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            BackgroundColor(BUTTON_UNSELECTED_COLOR),
+            BorderRadius::all(Val::VMax(2.0)),
+            Outline{
+                color: BUTTON_OUTLINE_COLOR,
+                width: Val::Percent(BUTTON_OUTLINE_UNSELECTED_WIDTH_PER),
+                ..Default::default()
+            },
+        )).with_child((
+            BindingLabel(action),
+            Text::new(label(action, settings.bindings.get(action), false)),
+            TextFont{
+                font_size: ROW_FONT_SIZE,
+                font_smoothing: FontSmoothing::AntiAliased,
+                ..Default::default()
+            },
+            BaseFontSize(ROW_FONT_SIZE),
+            TextColor(BUTTON_TEXT_COLOR),
+            TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+        ));
+    }
+    //make buttons
+    let total_width = BUTTON_WIDTH_PER * BUTTON_TEXT.len() as f32 + BUTTON_SPACING_PER * (BUTTON_TEXT.len() - 1) as f32;
+    for (i, &text) in BUTTON_TEXT.iter().enumerate(){
+        commands.spawn((
+            MenuUI,
+            Button,
+            ButtonIndex(Action::ALL.len() + i),
+            Node{
+                position_type: PositionType::Absolute,
+                width: Val::Percent(BUTTON_WIDTH_PER),
+                height: Val::Percent(BUTTON_HEIGHT_PER),
+                bottom: Val::Percent(BUTTON_BOTTOM_PER),
+                left: Val::Percent((100.0 - total_width) / 2.0 + (BUTTON_WIDTH_PER + BUTTON_SPACING_PER) * i as f32),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            BackgroundColor(BUTTON_UNSELECTED_COLOR),
+            BorderRadius::all(Val::VMax(5.0)),
+            Outline{
+                color: BUTTON_OUTLINE_COLOR,
+                width: Val::Percent(BUTTON_OUTLINE_UNSELECTED_WIDTH_PER),
+                ..Default::default()
+            },
+        )).with_child((
+            Text::new(text),
+            TextFont {
+                font_size: BUTTON_TEXT_FONT_SIZE,
+                font_smoothing: FontSmoothing::AntiAliased,
+                ..Default::default()
+            },
+            BaseFontSize(BUTTON_TEXT_FONT_SIZE),
+            TextColor(BUTTON_TEXT_COLOR),
+            TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+        ));
+    }
+}
+
+fn label(action: Action, binding: KeyBinding, waiting: bool) -> String{
+    if waiting{
+        format!("{}: press a key...", action.name())
+    }else{
+        format!("{}: {}", action.name(), binding.label())
+    }
+}
+
+pub fn transition(
+    player_index: ResMut<PlayerIndex>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+) -> TransitionType{
+    match **player_index{
+        i if i < Action::ALL.len() => {
+            commands.insert_resource(Rebinding{action: Action::ALL[i], armed: false});
+            TransitionType::Stay
+        },
+        i if i == Action::ALL.len() => {
+            settings.bindings.reset();
+            if let Err(e) = settings.save(&paths){
+                show_toast(&mut commands, &toasts, format!("Couldn't save settings: {}", e));
+            }
+            TransitionType::Stay
+        },
+        i if i == Action::ALL.len() + 1 => {
+            next_menu_state.set(MenuState::SettingsMenu);
+            TransitionType::Out
+        },
+        _ => panic!("somehow went into a non-existant menu"),
+    }
+}
+
+pub fn detransition(
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+){
+    next_menu_state.set(MenuState::SettingsMenu);
+}
+
+///binds the action being rebound to the next key pressed
+pub fn capture_key(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+    sounds: Res<MenuSounds>,
+){
+    if !rebinding.armed{
+        rebinding.armed = true;
+        return;
+    }
+    let volume = settings.volume(Bus::MenuEffects);
+    if inputs.just_pressed(KeyCode::Escape){
+        commands.remove_resource::<Rebinding>();
+        commands.spawn((AudioPlayer(sounds[&MenuSoundType::Back].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::MenuEffects));
+        return;
+    }
+    //shift only counts as part of another key
+    let Some(&key) = inputs.get_just_pressed().find(|&&key| key != KeyCode::ShiftLeft && key != KeyCode::ShiftRight) else{
+        return;
+    };
+
+    let shift = inputs.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    settings.bindings.set(rebinding.action, KeyBinding{key, shift});
+    if let Err(e) = settings.save(&paths){
+        show_toast(&mut commands, &toasts, format!("Couldn't save settings: {}", e));
+    }
+    commands.remove_resource::<Rebinding>();
+    commands.spawn((AudioPlayer(sounds[&MenuSoundType::Select].clone()), PlaybackSettings{mode: PlaybackMode::Despawn, volume, ..Default::default()}, Bus::MenuEffects));
+}
+
+///shows each action's key, or a prompt on the one being rebound
+pub fn update_bindings(
+    settings: Res<Settings>,
+    rebinding: Option<Res<Rebinding>>,
+    mut labels: Query<(&BindingLabel, &mut Text)>,
+){
+    for (action, mut text) in &mut labels{
+        let waiting = rebinding.as_ref().is_some_and(|r| r.action == **action);
+        let new_label = label(**action, settings.bindings.get(**action), waiting);
+        if text.0 != new_label{
+            text.0 = new_label;
+        }
+    }
+}
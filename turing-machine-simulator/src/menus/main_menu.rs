@@ -12,7 +12,7 @@ const BUTTON_HEIGHT_PER: f32 = 12.0;
 const BUTTON_OUTLINE_COLOR: Color = Color::BLACK;
 const BUTTON_SPACING_PER: f32 = 5.0;
 //button text
-const BUTTON_TEXT: [&'static str; 4] = ["Play Game!", "Settings", "Credits", "Quit"];
+const BUTTON_TEXT: [&str; 4] = ["Play Game!", "Settings", "Credits", "Quit"];
 const BUTTON_TEXT_COLOR: Color = Color::BLACK;
 const BUTTON_TEXT_FONT_SIZE: f32 = 60.0;
 
@@ -55,7 +55,7 @@ pub fn load(
         TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
     ));
     //make buttons
-    for i in 0..BUTTON_TEXT.len(){
+    for (i, &text) in BUTTON_TEXT.iter().enumerate(){
         commands.spawn((
             MenuUI,
             Button,
@@ -78,7 +78,7 @@ pub fn load(
                 ..Default::default()
             },
         )).with_child((
-            Text::new(BUTTON_TEXT[i]),
+            Text::new(text),
             TextFont {
                 font_size: BUTTON_TEXT_FONT_SIZE,
                 font_smoothing: FontSmoothing::AntiAliased,
@@ -1,29 +1,100 @@
 use bevy::{prelude::*};
+use bevy::input::{keyboard::{Key, KeyboardInput}, ButtonState};
 use bevy::text::FontSmoothing;
+use turing_core::slots::{self, SaveSummary};
 
-use crate::games::{GameState, SaveFileIndex};
+use crate::games::{GameState, SaveSlot};
+use crate::paths::AppPaths;
 use crate::menus::{MenuState, TransitionType};
+use crate::toast::{show_toast, Toast};
 use crate::{BaseFontSize, menus::{ButtonCount, ButtonIndex, PlayerIndex, BUTTON_OUTLINE_UNSELECTED_WIDTH_PER, BUTTON_UNSELECTED_COLOR, MenuUI}};
 //title
-const TITLE_HEIGHT_PER: f32 = 30.0;
+const TITLE_HEIGHT_PER: f32 = 20.0;
 const TITLE_WIDTH_PER: f32 = 90.0;
 const TITLE_FONT_SIZE: f32 = 80.0;
+//hint
+const HINT_TOP_PER: f32 = 16.0;
+const HINT_FONT_SIZE: f32 = 20.0;
+const HINT_TEXT: &str = "Enter: play   F2: rename   F3: duplicate   Delete: delete";
+const HINT_COLOR: Color = Color::WHITE;
+//save rows
+const VISIBLE_SAVE_COUNT: usize = 4;
+const ROW_WIDTH_PER: f32 = 80.0;
+const ROW_HEIGHT_PER: f32 = 13.0;
+const ROW_SPACING_PER: f32 = 2.5;
+const ROW_TOP_PER: f32 = 24.0;
+const ROW_NAME_FONT_SIZE: f32 = 34.0;
+const ROW_DETAIL_FONT_SIZE: f32 = 22.0;
 //buttons
-const BUTTON_WIDTH_PER: f32 = 60.0;
+const BUTTON_WIDTH_PER: f32 = 38.0;
 const BUTTON_HEIGHT_PER: f32 = 12.0;
 const BUTTON_OUTLINE_COLOR: Color = Color::BLACK;
-const BUTTON_SPACING_PER: f32 = 5.0;
+const BUTTON_SPACING_PER: f32 = 4.0;
+const BUTTON_BOTTOM_PER: f32 = 3.0;
 //button text
-const BUTTON_TEXT: [&'static str; 4] = ["Save 1", "Save 2", "Save 3", "Back"];
+const BUTTON_TEXT: [&str; 2] = ["New Save", "Back"];
 const BUTTON_TEXT_COLOR: Color = Color::BLACK;
-const BUTTON_TEXT_FONT_SIZE: f32 = 60.0;
+const BUTTON_TEXT_FONT_SIZE: f32 = 50.0;
+//new saves are called this followed by a number
+const NEW_SAVE_NAME: &str = "world";
 
+///every save on disk, and which of them are on screen
+#[derive(Resource, Default)]
+pub struct SaveList{
+    saves: Vec<SaveSummary>,
+    scroll: usize,
+    ///save waiting for a second delete press
+    pending_delete: Option<String>,
+}
+
+impl SaveList{
+    ///rereads the save directory
+    fn refresh(&mut self, paths: &AppPaths) -> Result<(), String>{
+        self.pending_delete = None;
+        match slots::list(&paths.saves_dir()){
+            Ok(saves) => {
+                self.saves = saves;
+                Ok(())
+            },
+            Err(e) => {
+                self.saves.clear();
+                Err(e.to_string())
+            },
+        }
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize>{
+        self.saves.iter().position(|s| s.name == name)
+    }
+}
+
+///name being typed for a save
+#[derive(Resource)]
+pub struct RenameBuffer{
+    original: String,
+    text: String,
+}
+
+///marker for the ui of the saves in the list
+#[derive(Component)]
+pub struct SaveRow;
+
+///buttons below the list, whose index depends on how many saves there are
+#[derive(Component, Deref)]
+pub struct ListButton(usize);
 
 pub fn load(
     mut commands: Commands,
     mut button_count: ResMut<ButtonCount>,
+    mut save_list: ResMut<SaveList>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
 ){
-    **button_count = BUTTON_TEXT.len();
+    if let Err(e) = save_list.refresh(&paths){
+        show_toast(&mut commands, &toasts, format!("Couldn't read saves: {}", e));
+    }
+    save_list.scroll = 0;
+    **button_count = save_list.saves.len() + BUTTON_TEXT.len();
     //title text
     commands.spawn((
         MenuUI,
@@ -37,10 +108,6 @@ pub fn load(
             ..Default::default()
         },
         BackgroundColor(Color::NONE),
-        // Transform{
-        //     rotation: Quat::from_rotation_z(std::f32::consts::PI / -30.0),
-        //     ..Default::default()
-        // },
     )).with_child((
         Text::new("Sandbox Mode"),
         TextFont{
@@ -52,18 +119,41 @@ pub fn load(
         TextColor(Color::WHITE),
         TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
     ));
+    //controls hint
+    commands.spawn((
+        MenuUI,
+        Node{
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Percent(HINT_TOP_PER),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+    )).with_child((
+        Text::new(HINT_TEXT),
+        TextFont{
+            font_size: HINT_FONT_SIZE,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(HINT_FONT_SIZE),
+        TextColor(HINT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+    ));
     //make buttons
-    for i in 0..BUTTON_TEXT.len(){
+    let total_width = BUTTON_WIDTH_PER * BUTTON_TEXT.len() as f32 + BUTTON_SPACING_PER * (BUTTON_TEXT.len() - 1) as f32;
+    for (i, &text) in BUTTON_TEXT.iter().enumerate(){
         commands.spawn((
             MenuUI,
             Button,
-            ButtonIndex(i),
+            ListButton(i),
+            ButtonIndex(save_list.saves.len() + i),
             Node{
                 position_type: PositionType::Absolute,
                 width: Val::Percent(BUTTON_WIDTH_PER),
                 height: Val::Percent(BUTTON_HEIGHT_PER),
-                bottom: Val::Percent(100.0 - TITLE_HEIGHT_PER - (BUTTON_HEIGHT_PER + BUTTON_SPACING_PER) * (i + 1) as f32),
-                left: Val::Percent((100.0 - BUTTON_WIDTH_PER) / 2.0),
+                bottom: Val::Percent(BUTTON_BOTTOM_PER),
+                left: Val::Percent((100.0 - total_width) / 2.0 + (BUTTON_WIDTH_PER + BUTTON_SPACING_PER) * i as f32),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..Default::default()
@@ -76,7 +166,7 @@ pub fn load(
                 ..Default::default()
             },
         )).with_child((
-            Text::new(BUTTON_TEXT[i]),
+            Text::new(text),
             TextFont {
                 font_size: BUTTON_TEXT_FONT_SIZE,
                 font_smoothing: FontSmoothing::AntiAliased,
@@ -89,19 +179,36 @@ pub fn load(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn transition(
     player_index: ResMut<PlayerIndex>,
-    mut save_file_index: ResMut<SaveFileIndex>,
+    mut save_slot: ResMut<SaveSlot>,
+    save_list: Res<SaveList>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
 ) -> TransitionType{
+    let save_count = save_list.saves.len();
     match **player_index{
-        0 | 1 | 2 => {next_menu_state.set(MenuState::None); next_game_state.set(GameState::Sandbox); **save_file_index = Some(**player_index + 1); },
-        3 => next_menu_state.set(MenuState::GameMenu),
+        i if i < save_count => {
+            next_menu_state.set(MenuState::None);
+            next_game_state.set(GameState::Sandbox);
+            **save_slot = Some(save_list.saves[i].name.clone());
+        },
+        //new save, the menu reloads with it at the top
+        i if i == save_count => {
+            if let Err(e) = slots::create(&paths.saves_dir(), NEW_SAVE_NAME){
+                show_toast(&mut commands, &toasts, format!("Couldn't create a save: {}", e));
+            }
+            next_menu_state.set(MenuState::SandboxMenu);
+        },
+        i if i == save_count + 1 => next_menu_state.set(MenuState::GameMenu),
         _ => panic!("somehow went into a non-existant menu"),
     }
 
-    if **player_index == 3{
+    if **player_index == save_count + 1{
         TransitionType::Out
     }
     else{
@@ -110,7 +217,254 @@ pub fn transition(
 }
 
 pub fn detransition(
-    mut next_menu_state: ResMut<NextState<MenuState>>, 
+    mut next_menu_state: ResMut<NextState<MenuState>>,
 ){
     next_menu_state.set(MenuState::GameMenu);
-}
\ No newline at end of file
+}
+
+///keeps the selected save on screen
+pub fn scroll_to_selection(
+    player_index: Res<PlayerIndex>,
+    mut save_list: ResMut<SaveList>,
+){
+    let index = (**player_index).min(save_list.saves.len().saturating_sub(1));
+    if index < save_list.scroll{
+        save_list.scroll = index;
+    }else if index >= save_list.scroll + VISIBLE_SAVE_COUNT{
+        save_list.scroll = index + 1 - VISIBLE_SAVE_COUNT;
+    }
+}
+
+///respawns the visible saves whenever the list, scroll or rename changes
+pub fn update_save_rows(
+    mut commands: Commands,
+    save_list: Res<SaveList>,
+    rename: Option<Res<RenameBuffer>>,
+    rows: Query<Entity, With<SaveRow>>,
+    mut list_buttons: Query<(&ListButton, &mut ButtonIndex)>,
+){
+    let renamed = rename.as_ref().is_some_and(|r| r.is_changed());
+    if !save_list.is_changed() && !renamed{
+        return;
+    }
+    for entity in &rows{
+        commands.entity(entity).despawn();
+    }
+    for (list_button, mut index) in &mut list_buttons{
+        **index = save_list.saves.len() + **list_button;
+    }
+
+    if save_list.saves.is_empty(){
+        spawn_list_text(&mut commands, ROW_TOP_PER, "No saves yet, make a new one!");
+        return;
+    }
+    if save_list.scroll > 0{
+        spawn_list_text(&mut commands, ROW_TOP_PER - ROW_SPACING_PER * 1.5, &format!("▲ {} more", save_list.scroll));
+    }
+    let below = save_list.saves.len().saturating_sub(save_list.scroll + VISIBLE_SAVE_COUNT);
+    if below > 0{
+        spawn_list_text(&mut commands, ROW_TOP_PER + (ROW_HEIGHT_PER + ROW_SPACING_PER) * VISIBLE_SAVE_COUNT as f32 - ROW_SPACING_PER, &format!("▼ {} more", below));
+    }
+
+    for (row, (i, save)) in save_list.saves.iter().enumerate().skip(save_list.scroll).take(VISIBLE_SAVE_COUNT).enumerate(){
+        let name = match &rename{
+            Some(r) if r.original == save.name => format!("{}_", r.text),
+            _ if save_list.pending_delete.as_ref() == Some(&save.name) => format!("Delete {}? Press Delete again", save.name),
+            _ => save.name.clone(),
+        };
+        let detail = match &save.preview{
+            Ok(preview) if preview.is_empty() => format!("{}   (empty tape)", format_timestamp(save.modified)),
+            Ok(preview) => format!("{}   {}", format_timestamp(save.modified), preview),
+            Err(e) => format!("{}   unreadable: {}", format_timestamp(save.modified), e),
+        };
+        commands.spawn((
+            MenuUI,
+            SaveRow,
+            Button,
+            ButtonIndex(i),
+            Node{
+                position_type: PositionType::Absolute,
+                width: Val::Percent(ROW_WIDTH_PER),
+                height: Val::Percent(ROW_HEIGHT_PER),
+                top: Val::Percent(ROW_TOP_PER + (ROW_HEIGHT_PER + ROW_SPACING_PER) * row as f32),
+                left: Val::Percent((100.0 - ROW_WIDTH_PER) / 2.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                overflow: Overflow::clip(),
+                ..Default::default()
+            },
+            BackgroundColor(BUTTON_UNSELECTED_COLOR),
+            BorderRadius::all(Val::VMax(2.0)),
+            Outline{
+                color: BUTTON_OUTLINE_COLOR,
+                width: Val::Percent(BUTTON_OUTLINE_UNSELECTED_WIDTH_PER),
+                ..Default::default()
+            },
+        )).with_children(|parent| {
+            parent.spawn((
+                Text::new(name),
+                TextFont{
+                    font_size: ROW_NAME_FONT_SIZE,
+                    font_smoothing: FontSmoothing::AntiAliased,
+                    ..Default::default()
+                },
+                BaseFontSize(ROW_NAME_FONT_SIZE),
+                TextColor(BUTTON_TEXT_COLOR),
+                TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+            ));
+            parent.spawn((
+                Text::new(detail),
+                TextFont{
+                    font_size: ROW_DETAIL_FONT_SIZE,
+                    font_smoothing: FontSmoothing::AntiAliased,
+                    ..Default::default()
+                },
+                BaseFontSize(ROW_DETAIL_FONT_SIZE),
+                TextColor(BUTTON_TEXT_COLOR),
+                TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+            ));
+        });
+    }
+}
+
+///spawns a line of text in the list area, like the scroll indicators
+fn spawn_list_text(
+    commands: &mut Commands,
+    top: f32,
+    text: &str,
+){
+    commands.spawn((
+        MenuUI,
+        SaveRow,
+        Node{
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Percent(top),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+    )).with_child((
+        Text::new(text),
+        TextFont{
+            font_size: HINT_FONT_SIZE,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(HINT_FONT_SIZE),
+        TextColor(HINT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
+    ));
+}
+
+///handles renaming, duplicating and deleting the selected save
+#[allow(clippy::too_many_arguments)]
+pub fn save_controls(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut keyboard: EventReader<KeyboardInput>,
+    mut player_index: ResMut<PlayerIndex>,
+    mut button_count: ResMut<ButtonCount>,
+    mut save_list: ResMut<SaveList>,
+    rename: Option<ResMut<RenameBuffer>>,
+    toasts: Query<Entity, With<Toast>>,
+    paths: Res<AppPaths>,
+){
+    let dir = &paths.saves_dir();
+
+    //typing a new name
+    if let Some(mut rename) = rename{
+        for e in keyboard.read(){
+            if e.state == ButtonState::Released{
+                continue;
+            }
+            match &e.logical_key{
+                Key::Character(c) => rename.text.push_str(c),
+                Key::Space => rename.text.push(' '),
+                Key::Backspace => {rename.text.pop();},
+                _ => (),
+            }
+        }
+
+        if inputs.just_pressed(KeyCode::Escape){
+            commands.remove_resource::<RenameBuffer>();
+            save_list.set_changed();
+        }else if inputs.just_pressed(KeyCode::Enter){
+            match slots::rename(dir, &rename.original, &rename.text){
+                Ok(()) => {
+                    commands.remove_resource::<RenameBuffer>();
+                    refresh(&mut commands, &toasts, &paths, &mut save_list, &mut button_count);
+                    if let Some(i) = save_list.index_of(&rename.text){
+                        **player_index = i;
+                    }
+                },
+                Err(e) => show_toast(&mut commands, &toasts, e.to_string()),
+            }
+        }
+        return;
+    }
+    keyboard.clear();
+
+    let Some(selected) = save_list.saves.get(**player_index).map(|s| s.name.clone()) else{
+        return;
+    };
+
+    if inputs.just_pressed(KeyCode::F2){
+        commands.insert_resource(RenameBuffer{original: selected.clone(), text: selected});
+    }else if inputs.just_pressed(KeyCode::F3){
+        match slots::duplicate(dir, &selected){
+            Ok(copy) => {
+                refresh(&mut commands, &toasts, &paths, &mut save_list, &mut button_count);
+                if let Some(i) = save_list.index_of(&copy){
+                    **player_index = i;
+                }
+            },
+            Err(e) => show_toast(&mut commands, &toasts, format!("Couldn't duplicate {}: {}", selected, e)),
+        }
+    }else if inputs.just_pressed(KeyCode::Delete){
+        if save_list.pending_delete.as_ref() != Some(&selected){
+            save_list.pending_delete = Some(selected);
+            return;
+        }
+        match slots::delete(dir, &selected){
+            Ok(()) => {
+                refresh(&mut commands, &toasts, &paths, &mut save_list, &mut button_count);
+                **player_index = (**player_index).min(**button_count - 1);
+            },
+            Err(e) => show_toast(&mut commands, &toasts, format!("Couldn't delete {}: {}", selected, e)),
+        }
+    }else if save_list.pending_delete.is_some() && inputs.get_just_pressed().next().is_some(){
+        //any other key cancels the delete
+        save_list.pending_delete = None;
+    }
+}
+
+fn refresh(
+    commands: &mut Commands,
+    toasts: &Query<Entity, With<Toast>>,
+    paths: &AppPaths,
+    save_list: &mut SaveList,
+    button_count: &mut ButtonCount,
+){
+    if let Err(e) = save_list.refresh(paths){
+        show_toast(commands, toasts, format!("Couldn't read saves: {}", e));
+    }
+    **button_count = save_list.saves.len() + BUTTON_TEXT.len();
+}
+
+///formats seconds since the unix epoch as a UTC date and time
+fn format_timestamp(secs: u64) -> String{
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+    //days to civil date, from Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, time / 3_600, time % 3_600 / 60)
+}
@@ -1,8 +1,7 @@
-use bevy::audio::Volume;
-use bevy::{prelude::*};
+use bevy::{prelude::*, window::PrimaryWindow};
 use bevy::text::FontSmoothing;
 
-use crate::{CurVolume, BASE_WINDOW_HEIGHT, BASE_WINDOW_WIDTH};
+use crate::{gamepad, paths::AppPaths, settings::{Bus, Settings}, toast::{show_toast, Toast}, BASE_WINDOW_HEIGHT, BASE_WINDOW_WIDTH};
 use crate::{menus::{PlayerIndex, TransitionType}, BaseFontSize, MenuState};
 
 use super::{MenuUI, ButtonIndex, ButtonCount, BUTTON_OUTLINE_UNSELECTED_WIDTH_PER, BUTTON_UNSELECTED_COLOR};
@@ -15,10 +14,12 @@ const SPACING: f32 = 5.0;
 const SUBTEXT_HEIGHT_PER: f32 = TEXT_HEIGHT_PER / 2.0;
 const SUBTEXT_FONT_SIZE: f32 = TEXT_FONT_SIZE / 2.0;
 //button
-const BUTTON_WIDTH_PER: f32 = 60.0;
+const BUTTON_WIDTH_PER: f32 = 38.0;
 const BUTTON_HEIGHT_PER: f32 = 12.0;
 const BUTTON_OUTLINE_COLOR: Color = Color::BLACK;
+const BUTTON_SPACING_PER: f32 = 4.0;
 //button text
+const BUTTON_TEXT: [&str; 2] = ["Controls", "Back"];
 const BUTTON_TEXT_COLOR: Color = Color::BLACK;
 const BUTTON_TEXT_FONT_SIZE: f32 = 60.0;
 //sliders
@@ -29,14 +30,29 @@ const SLIDER_COLOR: Color = Color::BLACK;
 const SLIDER_THUMB_HEIGHT: f32 = SLIDER_HEIGHT * 3.0;
 const SLIDER_THUMB_WIDTH: f32 = SLIDER_WIDTH / 100.0;
 const SLIDER_THUMB_COLOR: Color = Color::WHITE;
+const SLIDER_ROW_HEIGHT_PER: f32 = 13.0;
+const SLIDER_LABEL_HEIGHT_PER: f32 = BASE_WINDOW_HEIGHT / SLIDER_THUMB_HEIGHT;
+const MUTED_THUMB_COLOR: Color = Color::linear_rgb(0.5, 0.0, 0.0);
 //slider text
 const SLIDER_TEXT_COLOR: Color = Color::WHITE;
 const SLIDER_TEXT_FONT_SIZE: f32 = 30.0;
-const SLIDER_TEXT: [&'static str; 1] = ["Master Volume"];
+const SLIDER_TEXT: [&str; 3] = ["Master Volume", "Menu Effects", "Tape Effects"];
+///the bus each slider controls
+const SLIDER_BUSES: [Bus; SLIDER_TEXT.len()] = [Bus::Master, Bus::MenuEffects, Bus::TapeEffects];
+//hint
+const HINT_TEXT: &str = "Left/Right to adjust, M or the west face button to mute";
 
 #[derive(Component, Deref)]
 pub struct Slider(usize);
 
+///slider whose thumb is being dragged with the mouse
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct DraggedSlider(Option<usize>);
+
+///label of a slider, showing whether its bus is muted
+#[derive(Component, Deref)]
+pub struct SliderLabel(usize);
+
 #[derive(Component)]
 pub struct Thumb{
     index: usize,
@@ -48,9 +64,9 @@ pub fn load(
     mut button_count: ResMut<ButtonCount>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut mats: ResMut<Assets<ColorMaterial>>,
-    volume: Res<CurVolume>,
+    settings: Res<Settings>,
 ){
-    **button_count = SLIDER_TEXT.len() + 1;
+    **button_count = SLIDER_TEXT.len() + BUTTON_TEXT.len();
     //TEXT
     commands.spawn((
         MenuUI,
@@ -75,20 +91,45 @@ pub fn load(
         TextColor(Color::WHITE),
         TextLayout::new_with_justify(JustifyText::Center),
     ));
+    //HINT
+    commands.spawn((
+        MenuUI,
+        Node{
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(SUBTEXT_HEIGHT_PER / 2.0),
+            top: Val::Percent(TEXT_HEIGHT_PER - SUBTEXT_HEIGHT_PER / 2.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        BackgroundColor(Color::NONE),
+    )).with_child((
+        Text::new(HINT_TEXT),
+        TextFont{
+            font_size: SUBTEXT_FONT_SIZE / 2.0,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(SUBTEXT_FONT_SIZE / 2.0),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+    ));
 
     //Sliders
-    for i in 0..SLIDER_TEXT.len(){
-        //slider bar
+    for (i, &text) in SLIDER_TEXT.iter().enumerate(){
+        let top = TEXT_HEIGHT_PER + SLIDER_ROW_HEIGHT_PER * i as f32;
+        //slider bar, lined up with the middle of its label
         commands.spawn((
             MenuUI,
             Slider(i),
             Mesh2d(meshes.add(Rectangle::new(SLIDER_WIDTH, SLIDER_HEIGHT))),
             MeshMaterial2d(mats.add(SLIDER_COLOR)),
-            Transform::from_translation(Vec3::new(0.0, 110.0, 0.0))
+            Transform::from_translation(Vec3::new(0.0, BASE_WINDOW_HEIGHT * (0.5 - (top + SLIDER_LABEL_HEIGHT_PER / 2.0) / 100.0), 0.0))
         ))
         //slider thumb
         .with_child((
-            Thumb{index: i, location: volume.to_linear()},
+            Thumb{index: i, location: settings.channel(SLIDER_BUSES[i]).volume},
             Mesh2d(meshes.add(Rectangle::new(SLIDER_THUMB_WIDTH, SLIDER_THUMB_HEIGHT))),
             MeshMaterial2d(mats.add(SLIDER_COLOR)),
             Transform::from_translation(Vec3::new(0.0,0.0,1.0)),
@@ -98,8 +139,8 @@ pub fn load(
             Node{
                 position_type: PositionType::Absolute,
                 width: Val::Percent(BASE_WINDOW_WIDTH / SLIDER_WIDTH),
-                height: Val::Percent(BASE_WINDOW_HEIGHT / SLIDER_THUMB_HEIGHT),
-                top: Val::Percent((BASE_WINDOW_HEIGHT / SLIDER_THUMB_HEIGHT + SPACING) * i as f32 + TEXT_HEIGHT_PER),
+                height: Val::Percent(SLIDER_LABEL_HEIGHT_PER),
+                top: Val::Percent(top),
                 left: Val::Percent(10.0 + BASE_WINDOW_WIDTH / SLIDER_WIDTH),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
@@ -107,7 +148,8 @@ pub fn load(
             },
             BackgroundColor(Color::NONE),
         )).with_child((
-            Text::new(SLIDER_TEXT[i]),
+            SliderLabel(i),
+            Text::new(label(text, settings.channel(SLIDER_BUSES[i]).muted)),
             TextFont{
                 font_size: SLIDER_TEXT_FONT_SIZE,
                 font_smoothing: FontSmoothing::AntiAliased,
@@ -119,17 +161,19 @@ pub fn load(
         ));
     }
  
-    //EXIT BUTTON
-    commands.spawn((
+    //BUTTONS
+    let total_width = BUTTON_WIDTH_PER * BUTTON_TEXT.len() as f32 + BUTTON_SPACING_PER * (BUTTON_TEXT.len() - 1) as f32;
+    for (i, &text) in BUTTON_TEXT.iter().enumerate(){
+        commands.spawn((
             MenuUI,
             Button,
-            ButtonIndex(SLIDER_TEXT.len()),
+            ButtonIndex(SLIDER_TEXT.len() + i),
             Node{
                 position_type: PositionType::Absolute,
                 width: Val::Percent(BUTTON_WIDTH_PER),
                 height: Val::Percent(BUTTON_HEIGHT_PER),
                 bottom: Val::Percent(2.0),
-                left: Val::Percent((100.0 - BUTTON_WIDTH_PER) / 2.0),
+                left: Val::Percent((100.0 - total_width) / 2.0 + (BUTTON_WIDTH_PER + BUTTON_SPACING_PER) * i as f32),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..Default::default()
@@ -142,7 +186,7 @@ pub fn load(
                 ..Default::default()
             },
         )).with_child((
-            Text::new("Back"),
+            Text::new(text),
             TextFont {
                 font_size: BUTTON_TEXT_FONT_SIZE,
                 font_smoothing: FontSmoothing::AntiAliased,
@@ -152,17 +196,23 @@ pub fn load(
             TextColor(BUTTON_TEXT_COLOR),
             TextLayout::new_with_justify(JustifyText::Center).with_no_wrap(),
         ));
+    }
 }
 
 pub fn transition(
     player_index: ResMut<PlayerIndex>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
 ) -> TransitionType{
-    if **player_index == SLIDER_TEXT.len(){ 
-        next_menu_state.set(MenuState::MainMenu);
-        TransitionType::Out
-    }else{
-        TransitionType::In
+    match **player_index{
+        i if i < SLIDER_TEXT.len() => TransitionType::Stay,
+        i if i == SLIDER_TEXT.len() => {
+            next_menu_state.set(MenuState::ControlsMenu);
+            TransitionType::In
+        },
+        _ => {
+            next_menu_state.set(MenuState::MainMenu);
+            TransitionType::Out
+        },
     }
 }
 
@@ -172,48 +222,144 @@ pub fn detransition(
     next_menu_state.set(MenuState::MainMenu);
 }
 
+fn label(text: &str, muted: bool) -> String{
+    if muted {format!("{} (muted)", text)} else {text.to_string()}
+}
+
+///moves the thumbs to their volumes and highlights the selected one
 pub fn update_sliders(
     player_index: Res<PlayerIndex>,
+    settings: Res<Settings>,
     mut thumbs: Query<(&Thumb, &MeshMaterial2d<ColorMaterial>, &mut Transform)>,
+    mut labels: Query<(&SliderLabel, &mut Text)>,
     mut mats: ResMut<Assets<ColorMaterial>>,
 ){
     for (thumb,  color, mut transform) in &mut thumbs{
         transform.translation.x = SLIDER_WIDTH * (thumb.location - 0.5);
-        if **player_index == thumb.index{
-            mats.get_mut(color.id()).unwrap().color = Color::WHITE;
-        }else{
-            mats.get_mut(color.id()).unwrap().color = SLIDER_COLOR;
+        let muted = settings.channel(SLIDER_BUSES[thumb.index]).muted;
+        mats.get_mut(color.id()).unwrap().color = match (**player_index == thumb.index, muted){
+            (true, _) => SLIDER_THUMB_COLOR,
+            (false, true) => MUTED_THUMB_COLOR,
+            (false, false) => SLIDER_COLOR,
+        };
+    }
+    if settings.is_changed(){
+        for (index, mut text) in &mut labels{
+            text.0 = label(SLIDER_TEXT[**index], settings.channel(SLIDER_BUSES[**index]).muted);
         }
-    } 
+    }
 }
 
+///moves the selected slider and mutes its bus
+#[allow(clippy::too_many_arguments)]
 pub fn slider_controls(
     player_index: Res<PlayerIndex>,
     inputs: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
     mut thumbs: Query<&mut Thumb>,
     time: Res<Time>,
-    mut volume: ResMut<CurVolume>,
+    mut settings: ResMut<Settings>,
+    paths: Res<AppPaths>,
+    mut commands: Commands,
+    toasts: Query<Entity, With<Toast>>,
 ){
+    let Some(&bus) = SLIDER_BUSES.get(**player_index) else{
+        return;
+    };
     let dt = time.delta_secs();
-    if inputs.pressed(KeyCode::ArrowLeft){
+    let left = inputs.pressed(KeyCode::ArrowLeft) || gamepad::pressed(&gamepads, GamepadButton::DPadLeft);
+    let right = inputs.pressed(KeyCode::ArrowRight) || gamepad::pressed(&gamepads, GamepadButton::DPadRight);
+    let mute = inputs.just_pressed(KeyCode::KeyM) || gamepad::just_pressed(&gamepads, GamepadButton::West);
+    let direction = match (left, right){
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+    if direction != 0.0{
         for mut t in &mut thumbs{
             if t.index == **player_index{
-                t.location -= dt;
+                t.location += direction * dt;
                 t.location = t.location.clamp(0.0, 1.0);
-                volume.0 = Volume::Linear(t.location);
+                settings.channel_mut(bus).volume = t.location;
                 break;
             }
-        }      
+        }
     }
-    if inputs.pressed(KeyCode::ArrowRight){
-        for mut t in &mut thumbs{
-            if t.index == **player_index{
-                t.location += dt;
-                t.location = t.location.clamp(0.0, 1.0);
-                volume.0 = Volume::Linear(t.location);
-                break;
-            }
-        }      
+    if mute{
+        let channel = settings.channel_mut(bus);
+        channel.muted = !channel.muted;
+    }
+
+    //write once the slider is let go rather than every frame it moves
+    let released = inputs.any_just_released([KeyCode::ArrowLeft, KeyCode::ArrowRight])
+        || gamepad::just_released(&gamepads, GamepadButton::DPadLeft)
+        || gamepad::just_released(&gamepads, GamepadButton::DPadRight);
+    if released || mute{
+        save_settings(&settings, &paths, &mut commands, &toasts);
+    }
+}
+
+///grabbing a slider's bar selects it and moves its thumb to the cursor until the mouse is released
+#[allow(clippy::too_many_arguments)]
+pub fn drag_sliders(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    sliders: Query<(&Slider, &GlobalTransform)>,
+    mut thumbs: Query<&mut Thumb>,
+    mut dragged: ResMut<DraggedSlider>,
+    mut player_index: ResMut<PlayerIndex>,
+    mut settings: ResMut<Settings>,
+    paths: Res<AppPaths>,
+    mut commands: Commands,
+    toasts: Query<Entity, With<Toast>>,
+){
+    if mouse.just_released(MouseButton::Left) && dragged.take().is_some(){
+        save_settings(&settings, &paths, &mut commands, &toasts);
+        return;
     }
+    let Some(cursor) = windows.single().ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|position| {
+            let (camera, transform) = cameras.single().ok()?;
+            camera.viewport_to_world_2d(transform, position).ok()
+        })
+    else{
+        return;
+    };
 
-}
\ No newline at end of file
+    if mouse.just_pressed(MouseButton::Left){
+        **dragged = sliders.iter()
+            .find(|(_, bar)| {
+                let offset = cursor - bar.translation().truncate();
+                offset.x.abs() <= SLIDER_WIDTH / 2.0 && offset.y.abs() <= SLIDER_THUMB_HEIGHT / 2.0
+            })
+            .map(|(slider, _)| **slider);
+    }
+    let Some(index) = **dragged else{
+        return;
+    };
+    let Some((_, bar)) = sliders.iter().find(|(slider, _)| ***slider == index) else{
+        return;
+    };
+
+    let location = ((cursor.x - bar.translation().x) / SLIDER_WIDTH + 0.5).clamp(0.0, 1.0);
+    **player_index = index;
+    for mut t in &mut thumbs{
+        if t.index == index{
+            t.location = location;
+        }
+    }
+    settings.channel_mut(SLIDER_BUSES[index]).volume = location;
+}
+
+fn save_settings(
+    settings: &Settings,
+    paths: &AppPaths,
+    commands: &mut Commands,
+    toasts: &Query<Entity, With<Toast>>,
+){
+    if let Err(e) = settings.save(paths){
+        show_toast(commands, toasts, format!("Couldn't save settings: {}", e));
+    }
+}
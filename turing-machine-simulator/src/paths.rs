@@ -0,0 +1,91 @@
+use std::{fs, io, path::PathBuf};
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+
+use crate::cli::Args;
+
+///name of the folder made inside the platform's data and config directories
+const APP_DIR_NAME: &str = "turing-machine-simulator";
+///environment variables that override the data and config directories
+const DATA_DIR_ENV: &str = "TURING_MACHINE_SIMULATOR_DATA_DIR";
+const CONFIG_DIR_ENV: &str = "TURING_MACHINE_SIMULATOR_CONFIG_DIR";
+///where saves lived before they moved to the data directory, relative to the asset root
+const LEGACY_SAVE_DIRECTORY: &str = "assets/saves";
+const SAVE_DIRECTORY_NAME: &str = "saves";
+///where old saves are copied before the saves directory is made from it
+const MIGRATING_DIRECTORY_NAME: &str = "saves.migrating";
+const DIAGRAM_DIRECTORY_NAME: &str = "diagrams";
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+const LEGACY_SAVE_EXTENSIONS: [&str; 2] = ["sav", "tm"];
+
+///where the player's files live
+#[derive(Resource, Clone, Debug)]
+pub struct AppPaths{
+    ///saves and other things the player makes
+    pub data_dir: PathBuf,
+    ///settings
+    pub config_dir: PathBuf,
+}
+
+impl AppPaths{
+    ///picks the data and config directories from the command line, then the environment, then the platform's defaults.
+    ///on linux the defaults are `$XDG_DATA_HOME` and `$XDG_CONFIG_HOME`
+    pub fn resolve(args: &Args) -> Self{
+        let data_dir = args.data_dir.clone()
+            .or_else(|| std::env::var_os(DATA_DIR_ENV).map(PathBuf::from))
+            .or_else(|| dirs::data_dir().map(|d| d.join(APP_DIR_NAME)))
+            .unwrap_or_else(|| PathBuf::from(APP_DIR_NAME));
+        let config_dir = args.config_dir.clone()
+            .or_else(|| std::env::var_os(CONFIG_DIR_ENV).map(PathBuf::from))
+            .or_else(|| dirs::config_dir().map(|d| d.join(APP_DIR_NAME)))
+            .unwrap_or_else(|| data_dir.clone());
+        Self{
+            data_dir,
+            config_dir,
+        }
+    }
+
+    pub fn saves_dir(&self) -> PathBuf{
+        self.data_dir.join(SAVE_DIRECTORY_NAME)
+    }
+
+    ///state diagrams exported from the game
+    pub fn diagrams_dir(&self) -> PathBuf{
+        self.data_dir.join(DIAGRAM_DIRECTORY_NAME)
+    }
+
+    pub fn settings_file(&self) -> PathBuf{
+        self.config_dir.join(SETTINGS_FILE_NAME)
+    }
+
+    ///copies saves from the old `assets/saves` folder next to the game the first time the saves directory is made,
+    ///returning how many files were copied
+    pub fn migrate_legacy_saves(&self) -> io::Result<usize>{
+        let saves_dir = self.saves_dir();
+        if saves_dir.exists(){
+            return Ok(0);
+        }
+
+        let entries = match fs::read_dir(FileAssetReader::get_base_path().join(LEGACY_SAVE_DIRECTORY)){
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return fs::create_dir_all(&saves_dir).map(|_| 0),
+            Err(e) => return Err(e),
+        };
+        //the saves directory only appears once everything is copied, so a failed copy is tried again next time
+        let migrating_dir = self.data_dir.join(MIGRATING_DIRECTORY_NAME);
+        if migrating_dir.exists(){
+            fs::remove_dir_all(&migrating_dir)?;
+        }
+        fs::create_dir_all(&migrating_dir)?;
+        let mut copied = 0;
+        for entry in entries{
+            let path = entry?.path();
+            let is_save = path.extension().and_then(|e| e.to_str()).is_some_and(|e| LEGACY_SAVE_EXTENSIONS.contains(&e));
+            if let (true, Some(file_name)) = (is_save, path.file_name()){
+                fs::copy(&path, migrating_dir.join(file_name))?;
+                copied += 1;
+            }
+        }
+        fs::rename(&migrating_dir, &saves_dir)?;
+        Ok(copied)
+    }
+}
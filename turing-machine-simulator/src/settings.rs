@@ -0,0 +1,135 @@
+use std::{fs, io, path::Path};
+use bevy::{audio::Volume, prelude::*};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use turing_core::explore::DEFAULT_EXPLORATION_LIMIT;
+
+use crate::{actions::Bindings, paths::AppPaths};
+
+///a group of sounds whose volume is set together.
+///every sound is scaled by its own bus and by the master bus
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bus{
+    Master,
+    MenuEffects,
+    TapeEffects,
+    ///nothing plays on it yet, so the settings menu has no slider for it
+    Music,
+}
+
+///volume and mute toggle of a bus
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Channel{
+    ///linear volume from 0 to 1
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for Channel{
+    fn default() -> Self {
+        Self{
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Channel{
+    fn level(&self) -> f32{
+        if self.muted {0.0} else {self.volume}
+    }
+}
+
+///everything the player can change in the settings menu.
+///fields missing from the file keep their defaults, so new settings can be added without breaking old files
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings{
+    pub master: Channel,
+    pub menu_effects: Channel,
+    pub tape_effects: Channel,
+    pub music: Channel,
+    pub bindings: Bindings,
+    ///configurations the computation tree explores before giving up
+    pub exploration_limit: usize,
+}
+
+impl Default for Settings{
+    fn default() -> Self {
+        Self{
+            master: Channel::default(),
+            menu_effects: Channel::default(),
+            tape_effects: Channel::default(),
+            music: Channel::default(),
+            bindings: Bindings::default(),
+            exploration_limit: DEFAULT_EXPLORATION_LIMIT,
+        }
+    }
+}
+
+impl Settings{
+    ///reads the settings file, falling back to the defaults if there isn't one yet
+    pub fn load(paths: &AppPaths) -> Result<Self, String>{
+        let path = paths.settings_file();
+        match fs::read_to_string(&path){
+            Ok(contents) => ron::from_str(&contents).map_err(|e| format!("{} is corrupted: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("could not read {}: {}", path.display(), e)),
+        }
+    }
+
+    ///writes the settings file, replacing it only once the new one is fully written
+    pub fn save(&self, paths: &AppPaths) -> Result<(), String>{
+        let path = paths.settings_file();
+        let contents = ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(|e| format!("could not encode settings: {}", e))?;
+        write_atomically(&path, &contents).map_err(|e| format!("could not write {}: {}", path.display(), e))
+    }
+
+    pub fn channel(&self, bus: Bus) -> &Channel{
+        match bus{
+            Bus::Master => &self.master,
+            Bus::MenuEffects => &self.menu_effects,
+            Bus::TapeEffects => &self.tape_effects,
+            Bus::Music => &self.music,
+        }
+    }
+
+    pub fn channel_mut(&mut self, bus: Bus) -> &mut Channel{
+        match bus{
+            Bus::Master => &mut self.master,
+            Bus::MenuEffects => &mut self.menu_effects,
+            Bus::TapeEffects => &mut self.tape_effects,
+            Bus::Music => &mut self.music,
+        }
+    }
+
+    ///how loud a sound on `bus` should play, taking the master bus into account
+    pub fn volume(&self, bus: Bus) -> Volume{
+        let level = match bus{
+            Bus::Master => self.master.level(),
+            _ => self.master.level() * self.channel(bus).level(),
+        };
+        Volume::Linear(level)
+    }
+}
+
+///keeps sounds that are already playing in step with the settings
+pub fn apply_volumes(
+    settings: Res<Settings>,
+    mut sinks: Query<(&Bus, &mut AudioSink)>,
+){
+    for (&bus, mut sink) in &mut sinks{
+        sink.set_volume(settings.volume(bus));
+    }
+}
+
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()>{
+    if let Some(parent) = path.parent(){
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("ron.tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)
+}
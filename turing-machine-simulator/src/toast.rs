@@ -0,0 +1,75 @@
+use bevy::{prelude::*, text::FontSmoothing};
+
+use crate::BaseFontSize;
+
+const TOAST_DURATION_SECS: f32 = 4.0;
+const TOAST_WIDTH_PER: f32 = 80.0;
+const TOAST_BOTTOM_PER: f32 = 3.0;
+const TOAST_COLOR: Color = Color::linear_rgba(0.0, 0.0, 0.0, 0.85);
+const TOAST_TEXT_COLOR: Color = Color::WHITE;
+const TOAST_FONT_SIZE: f32 = 24.0;
+
+///a short message shown at the bottom of the screen that survives menu and game transitions
+#[derive(Component, Deref, DerefMut)]
+pub struct Toast(Timer);
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin{
+    fn build(&self, app: &mut App){
+        app
+        .add_systems(
+            Update,
+            expire_toasts
+        );
+    }
+}
+
+///shows `message` for a few seconds, replacing any toast already on screen
+pub fn show_toast(
+    commands: &mut Commands,
+    toasts: &Query<Entity, With<Toast>>,
+    message: impl Into<String>,
+){
+    for entity in toasts{
+        commands.entity(entity).despawn();
+    }
+    commands.spawn((
+        Toast(Timer::from_seconds(TOAST_DURATION_SECS, TimerMode::Once)),
+        Node{
+            position_type: PositionType::Absolute,
+            width: Val::Percent(TOAST_WIDTH_PER),
+            bottom: Val::Percent(TOAST_BOTTOM_PER),
+            left: Val::Percent((100.0 - TOAST_WIDTH_PER) / 2.0),
+            padding: UiRect::all(Val::Percent(1.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        BackgroundColor(TOAST_COLOR),
+        BorderRadius::all(Val::VMax(1.0)),
+        GlobalZIndex(i32::MAX),
+    )).with_child((
+        Text::new(message),
+        TextFont{
+            font_size: TOAST_FONT_SIZE,
+            font_smoothing: FontSmoothing::AntiAliased,
+            ..Default::default()
+        },
+        BaseFontSize(TOAST_FONT_SIZE),
+        TextColor(TOAST_TEXT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+    ));
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut Toast)>,
+    time: Res<Time>,
+){
+    for (entity, mut toast) in &mut toasts{
+        if toast.tick(time.delta()).finished(){
+            commands.entity(entity).despawn();
+        }
+    }
+}
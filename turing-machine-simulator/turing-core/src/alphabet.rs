@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+
+use crate::{machine::Machine, tape::{Tape, BLANK}};
+
+/// symbols in the input alphabet of a new machine
+const DEFAULT_INPUT: [char; 2] = ['0', '1'];
+
+/// the symbols a machine works with.
+///
+/// like the formal definition, the input alphabet is part of the tape alphabet
+/// and the blank is on the tape but never part of the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet{
+    input: BTreeSet<char>,
+    tape: BTreeSet<char>,
+    blank: char,
+}
+
+impl Default for Alphabet{
+    fn default() -> Self {
+        Self::new(DEFAULT_INPUT, [], BLANK).expect("the default alphabet is valid")
+    }
+}
+
+impl Alphabet{
+    /// an alphabet whose tape symbols are `tape` along with the input symbols and the blank
+    pub fn new(input: impl IntoIterator<Item = char>, tape: impl IntoIterator<Item = char>, blank: char) -> Result<Self, String>{
+        let input: BTreeSet<char> = input.into_iter().collect();
+        if input.contains(&blank){
+            return Err(format!("the blank '{}' can't be an input symbol", blank));
+        }
+        let tape = tape.into_iter().chain(input.iter().copied()).chain([blank]).collect();
+        Ok(Self{
+            input,
+            tape,
+            blank,
+        })
+    }
+
+    /// the default alphabet, plus every symbol on `tapes` and in `machine`.
+    /// used for worlds made before machines had alphabets
+    pub fn covering(tapes: &[Tape], machine: &Machine) -> Self{
+        let blank = tapes.first().map_or(BLANK, Tape::blank);
+        let used = tapes.iter().flat_map(|tape| tape.iter().map(|(_, c)| c))
+            .chain(machine.transitions.iter().flat_map(|((_, read), choices)| read.iter().chain(choices.iter().flat_map(|t| &t.write)).copied()));
+        Self::new(DEFAULT_INPUT, used, blank).unwrap_or_default()
+    }
+
+    pub fn blank(&self) -> char{
+        self.blank
+    }
+
+    pub fn input(&self) -> impl Iterator<Item = char> + '_{
+        self.input.iter().copied()
+    }
+
+    /// every symbol that can be on the tape, including the blank
+    pub fn tape(&self) -> impl Iterator<Item = char> + '_{
+        self.tape.iter().copied()
+    }
+
+    pub fn is_input(&self, symbol: char) -> bool{
+        self.input.contains(&symbol)
+    }
+
+    pub fn is_tape(&self, symbol: char) -> bool{
+        self.tape.contains(&symbol)
+    }
+
+    /// checks that every transition reads and writes symbols of the tape alphabet
+    pub fn check(&self, machine: &Machine) -> Result<(), String>{
+        let mut transitions: Vec<_> = machine.transitions.iter().collect();
+        transitions.sort_by(|a, b| a.0.cmp(b.0));
+        for ((state, read), choices) in transitions{
+            for &symbol in read.iter().chain(choices.iter().flat_map(|t| &t.write)){
+                if !self.is_tape(symbol){
+                    let read: String = read.iter().collect();
+                    return Err(format!("the transition from {} on \"{}\" uses '{}', which isn't in the tape alphabet", state, read, symbol));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+//! the text format machines are written in, usually kept in `.tm` files.
+//!
+//! a description is read line by line. blank lines and lines starting with `#` are ignored,
+//! every other line is either a directive or a transition:
+//!
+//! ```text
+//! description = { line } ;
+//! line        = directive | transition | comment | "" ;
+//! comment     = "#" { any character } ;
+//!
+//! directive   = "states:" state { state }       (* the only states transitions may use *)
+//!             | "start:" state                  (* defaults to q0 *)
+//!             | "accept:" state                 (* defaults to accept *)
+//!             | "reject:" state                 (* defaults to reject *)
+//!             | "input:" [ symbols ]            (* input alphabet, defaults to 01 *)
+//!             | "tape:" [ symbols ]             (* extra tape symbols besides the input and blank *)
+//!             | "blank:" symbol                 (* defaults to _ *)
+//!             | "tapes:" number                 (* defaults to what the first transition uses *)
+//!             | "initial:" [ "@" offset ] [ symbols ] ;  (* the next tape's contents *)
+//!
+//! transition  = state reads state writes moves ;
+//! reads       = symbol { symbol } ;             (* one per tape, written together *)
+//! writes      = symbol { symbol } ;
+//! moves       = move { move } ;
+//! move        = "L" | "R" | "S" | "l" | "r" | "s" | "N" | "n" | "<" | ">" | "-" ;
+//!
+//! state       = word ;                          (* anything without whitespace *)
+//! symbols     = word ;                          (* every character is a symbol *)
+//! symbol      = character other than whitespace ;
+//! ```
+//!
+//! words are separated by whitespace, so whitespace can't be a symbol. the first `initial:` line
+//! fills the first tape, the second the second, and so on, starting at cell `offset` or 0.
+//! once any of `input:`, `tape:` or `blank:` is given every transition and initial cell must stay
+//! within the alphabet, otherwise the alphabet is worked out from the symbols used.
+//! giving a state and read symbols more than one transition makes the machine nondeterministic.
+//!
+//! ```text
+//! # accepts binary strings with an even number of 1s
+//! states: even odd accept reject
+//! start: even
+//! input: 01
+//! initial: 1011
+//!
+//! even 0 even 0 R
+//! even 1 odd 1 R
+//! odd 0 odd 0 R
+//! odd 1 even 1 R
+//! even _ accept _ S
+//! odd _ reject _ S
+//! ```
+//!
+//! errors point at the line and column, counted from 1, of the word that caused them.
+
+use std::{collections::{HashMap, HashSet}, error::Error, fmt};
+
+use crate::{alphabet::Alphabet, machine::{Machine, Move, Transition}, tape::{Tape, BLANK}, Simulation};
+
+const COMMENT_PREFIX: char = '#';
+const DIRECTIVE_SUFFIX: char = ':';
+const OFFSET_PREFIX: char = '@';
+
+/// where something is in a description, both counted from 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position{
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position{
+    /// `offset` characters further along the line
+    fn plus(self, offset: usize) -> Self{
+        Self{column: self.column + offset, ..self}
+    }
+}
+
+/// why a description couldn't be read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError{
+    pub position: Position,
+    pub message: String,
+}
+
+impl ParseError{
+    pub(crate) fn new(position: Position, message: impl Into<String>) -> Self{
+        Self{
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{}:{}: {}", self.position.line, self.position.column, self.message)
+    }
+}
+
+impl Error for ParseError{}
+
+/// a machine along with the tapes it starts on
+#[derive(Clone, Debug)]
+pub struct Description{
+    pub machine: Machine,
+    /// whether the alphabet was given rather than worked out from the symbols used
+    pub declares_alphabet: bool,
+    /// one per tape of the machine
+    pub tapes: Vec<Tape>,
+}
+
+/// a word of a line along with where it starts
+#[derive(Clone, Copy)]
+struct Word<'a>{
+    text: &'a str,
+    position: Position,
+}
+
+/// splits a line into words, remembering their columns
+fn words(line_number: usize, line: &str) -> Vec<Word<'_>>{
+    let mut words = Vec::new();
+    let mut start = None;
+    for (column, (byte, c)) in line.char_indices().enumerate(){
+        match (c.is_whitespace(), start){
+            (false, None) => start = Some((byte, column)),
+            (true, Some((start_byte, start_column))) => {
+                words.push(Word{text: &line[start_byte..byte], position: Position{line: line_number, column: start_column + 1}});
+                start = None;
+            },
+            _ => (),
+        }
+    }
+    if let Some((start_byte, start_column)) = start{
+        words.push(Word{text: &line[start_byte..], position: Position{line: line_number, column: start_column + 1}});
+    }
+    words
+}
+
+/// everything gathered while reading, checked once the whole description is read
+#[derive(Default)]
+struct Reader{
+    machine: Machine,
+    /// the line each single-use directive was given on
+    given: HashMap<String, usize>,
+    declared_states: Option<HashSet<String>>,
+    state_uses: Vec<(String, Position)>,
+    symbol_uses: Vec<(char, Position)>,
+    input: Option<(Vec<char>, Position)>,
+    tape_symbols: Option<(Vec<char>, Position)>,
+    blank: Option<(char, Position)>,
+    /// the number of tapes and the line that decided it
+    tape_count: Option<(usize, usize)>,
+    /// offset, where the cells were given and the cells of each `initial:` line
+    initial: Vec<(i64, Position, String)>,
+}
+
+impl Description{
+    /// reads a description written in the format above
+    pub fn parse(text: &str) -> Result<Self, ParseError>{
+        let mut reader = Reader::default();
+        for (index, line) in text.lines().enumerate(){
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with(COMMENT_PREFIX){
+                continue;
+            }
+            let words = words(index + 1, line);
+            match words[0].text.strip_suffix(DIRECTIVE_SUFFIX){
+                Some(name) => reader.directive(name, &words)?,
+                None => reader.transition(&words, line.chars().count())?,
+            }
+        }
+        reader.finish()
+    }
+
+    /// a simulation of the machine over its initial tapes
+    pub fn into_simulation(self) -> Simulation{
+        Simulation::new(self.tapes, self.machine)
+    }
+}
+
+impl Reader{
+    fn directive(&mut self, name: &str, words: &[Word]) -> Result<(), ParseError>{
+        let directive = words[0];
+        let values = &words[1..];
+        let single_use = !matches!(name, "states" | "initial");
+        if single_use{
+            if let Some(line) = self.given.get(name){
+                return Err(ParseError::new(directive.position, format!("{}: was already given on line {}", name, line)));
+            }
+            self.given.insert(name.to_string(), directive.position.line);
+        }
+
+        match name{
+            "states" => {
+                if values.is_empty(){
+                    return Err(ParseError::new(directive.position, "states: needs at least one state"));
+                }
+                self.declared_states.get_or_insert_default().extend(values.iter().map(|word| word.text.to_string()));
+            },
+            "start" | "accept" | "reject" => {
+                let state = exactly_one(directive, values)?;
+                self.state_uses.push((state.text.to_string(), state.position));
+                let field = match name{
+                    "start" => &mut self.machine.start_state,
+                    "accept" => &mut self.machine.accept_state,
+                    _ => &mut self.machine.reject_state,
+                };
+                *field = state.text.to_string();
+            },
+            "input" => {
+                let symbols = at_most_one(directive, values)?;
+                let position = symbols.map_or(directive.position, |word| word.position);
+                self.input = Some((symbols.map_or(Vec::new(), |word| word.text.chars().collect()), position));
+            },
+            "tape" => {
+                let symbols = at_most_one(directive, values)?;
+                let position = symbols.map_or(directive.position, |word| word.position);
+                self.tape_symbols = Some((symbols.map_or(Vec::new(), |word| word.text.chars().collect()), position));
+            },
+            "blank" => {
+                let symbol = exactly_one(directive, values)?;
+                let mut chars = symbol.text.chars();
+                match (chars.next(), chars.next()){
+                    (Some(c), None) => self.blank = Some((c, symbol.position)),
+                    _ => return Err(ParseError::new(symbol.position, "the blank must be a single character")),
+                }
+            },
+            "tapes" => {
+                let count = exactly_one(directive, values)?;
+                match count.text.parse::<usize>(){
+                    Ok(n) if n > 0 => self.set_tape_count(n, count.position)?,
+                    _ => return Err(ParseError::new(count.position, format!("\"{}\" isn't a number of tapes", count.text))),
+                }
+            },
+            "initial" => {
+                let (offset, cells) = match values{
+                    [offset, rest @ ..] if offset.text.starts_with(OFFSET_PREFIX) => {
+                        let parsed = offset.text[OFFSET_PREFIX.len_utf8()..].parse::<i64>()
+                            .map_err(|_| ParseError::new(offset.position, format!("\"{}\" isn't an offset like @-2", offset.text)))?;
+                        (parsed, rest)
+                    },
+                    _ => (0, values),
+                };
+                let cells = at_most_one(directive, cells)?;
+                let text = cells.map_or(String::new(), |word| word.text.to_string());
+                let position = cells.map_or(directive.position, |word| word.position);
+                for (i, c) in text.chars().enumerate(){
+                    self.symbol_uses.push((c, position.plus(i)));
+                }
+                self.initial.push((offset, position, text));
+            },
+            _ => return Err(ParseError::new(directive.position, format!("unknown directive \"{}\"", directive.text))),
+        }
+        Ok(())
+    }
+
+    fn transition(&mut self, words: &[Word], line_length: usize) -> Result<(), ParseError>{
+        let line = words[0].position.line;
+        match words.len(){
+            5 => (),
+            n if n > 5 => return Err(ParseError::new(words[5].position, "expected 5 fields: state read next_state write move")),
+            n => return Err(ParseError::new(Position{line, column: line_length + 1}, format!("expected 5 fields: state read next_state write move, found {}", n))),
+        }
+        let [state, read, next_state, write, moves] = [words[0], words[1], words[2], words[3], words[4]];
+
+        let read_symbols: Vec<char> = read.text.chars().collect();
+        self.set_tape_count(read_symbols.len(), read.position)?;
+        let tape_count = read_symbols.len();
+        let write_symbols: Vec<char> = write.text.chars().collect();
+        if write_symbols.len() != tape_count{
+            return Err(ParseError::new(write.position, format!("expected {} write symbols, one per tape, found {}", tape_count, write_symbols.len())));
+        }
+        let mut movement = Vec::new();
+        for (i, c) in moves.text.chars().enumerate(){
+            let parsed = Move::parse(&c.to_string())
+                .ok_or(ParseError::new(moves.position.plus(i), format!("unknown move '{}', use L, R or S", c)))?;
+            movement.push(parsed);
+        }
+        if movement.len() != tape_count{
+            return Err(ParseError::new(moves.position, format!("expected {} moves, one per tape, found {}", tape_count, movement.len())));
+        }
+
+        for (word, symbols) in [(read, &read_symbols), (write, &write_symbols)]{
+            for (i, &c) in symbols.iter().enumerate(){
+                self.symbol_uses.push((c, word.position.plus(i)));
+            }
+        }
+        self.state_uses.push((state.text.to_string(), state.position));
+        self.state_uses.push((next_state.text.to_string(), next_state.position));
+        self.machine.add_transition(state.text.to_string(), read_symbols, Transition{
+            next_state: next_state.text.to_string(),
+            write: write_symbols,
+            movement,
+        });
+        Ok(())
+    }
+
+    /// the first of `tapes:` or a transition decides how many tapes there are, the rest must agree
+    fn set_tape_count(&mut self, count: usize, position: Position) -> Result<(), ParseError>{
+        match self.tape_count{
+            None => self.tape_count = Some((count, position.line)),
+            Some((expected, line)) if expected != count => {
+                return Err(ParseError::new(position, format!("expected {} symbols, one per tape, as set on line {}", expected, line)));
+            },
+            Some(_) => (),
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Description, ParseError>{
+        if let Some(declared) = &self.declared_states
+            && let Some((state, position)) = self.state_uses.iter().find(|(state, _)| !declared.contains(state)){
+            return Err(ParseError::new(*position, format!("\"{}\" isn't one of the states given in states:", state)));
+        }
+
+        let tape_count = self.tape_count.map_or(self.initial.len().max(1), |(count, _)| count);
+        if let Some((_, position, _)) = self.initial.get(tape_count){
+            return Err(ParseError::new(*position, format!("there are only {} tapes to fill", tape_count)));
+        }
+        self.machine.tape_count = tape_count;
+
+        let declares_alphabet = self.input.is_some() || self.tape_symbols.is_some() || self.blank.is_some();
+        let blank = self.blank.map_or(BLANK, |(c, _)| c);
+        if declares_alphabet{
+            let (input, input_position) = match self.input{
+                Some((input, position)) => (input, Some(position)),
+                None => (Alphabet::default().input().collect(), None),
+            };
+            let (tape_symbols, tape_position) = self.tape_symbols.map_or((Vec::new(), None), |(symbols, position)| (symbols, Some(position)));
+            let position = self.blank.map(|(_, position)| position).or(input_position).or(tape_position).unwrap_or(Position{line: 1, column: 1});
+            self.machine.alphabet = Alphabet::new(input, tape_symbols, blank)
+                .map_err(|e| ParseError::new(position, e))?;
+            if let Some((c, position)) = self.symbol_uses.iter().find(|(c, _)| !self.machine.alphabet.is_tape(*c)){
+                return Err(ParseError::new(*position, format!("'{}' isn't in the tape alphabet", c)));
+            }
+        }
+
+        let mut tapes: Vec<Tape> = self.initial.iter()
+            .map(|(offset, _, cells)| {
+                let mut tape = Tape::new(blank);
+                for (i, c) in cells.chars().enumerate(){
+                    tape.set(offset + i as i64, c);
+                }
+                tape
+            })
+            .collect();
+        tapes.resize_with(tape_count, || Tape::new(blank));
+        if !declares_alphabet{
+            self.machine.alphabet = Alphabet::covering(&tapes, &self.machine);
+        }
+
+        Ok(Description{
+            machine: self.machine,
+            declares_alphabet,
+            tapes,
+        })
+    }
+}
+
+fn exactly_one<'a>(directive: Word, values: &[Word<'a>]) -> Result<Word<'a>, ParseError>{
+    match values{
+        [value] => Ok(*value),
+        [] => Err(ParseError::new(directive.position, format!("{} needs a value", directive.text))),
+        [_, extra, ..] => Err(ParseError::new(extra.position, format!("{} takes a single value", directive.text))),
+    }
+}
+
+fn at_most_one<'a>(directive: Word, values: &[Word<'a>]) -> Result<Option<Word<'a>>, ParseError>{
+    match values{
+        [] => Ok(None),
+        _ => exactly_one(directive, values).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// where parsing `text` failed and why
+    fn error(text: &str) -> (usize, usize, String){
+        let error = Description::parse(text).expect_err("the description should be rejected");
+        (error.position.line, error.position.column, error.message)
+    }
+
+    #[test]
+    fn parses_the_example(){
+        let text = "# accepts binary strings with an even number of 1s\nstates: even odd accept reject\nstart: even\ninput: 01\ninitial: 1011\n\neven 0 even 0 R\neven 1 odd 1 R\nodd 0 odd 0 R\nodd 1 even 1 R\neven _ accept _ S\nodd _ reject _ S\n";
+        let description = Description::parse(text).unwrap();
+        assert!(description.declares_alphabet);
+        assert_eq!(description.machine.start_state, "even");
+        assert_eq!(description.machine.tape_count, 1);
+        assert_eq!(description.machine.transitions.len(), 6);
+        assert_eq!(description.tapes[0].iter().map(|(_, c)| c).collect::<String>(), "1011");
+        let mut simulation = description.into_simulation();
+        assert_eq!(simulation.run(100), crate::Status::Rejected);
+    }
+
+    #[test]
+    fn initial_tapes_take_an_offset(){
+        let description = Description::parse("tapes: 2\ninitial: @-2 ab\ninitial: c").unwrap();
+        assert_eq!(description.tapes.len(), 2);
+        assert_eq!(description.tapes[0].iter().collect::<Vec<_>>(), vec![(-2, 'a'), (-1, 'b')]);
+        assert_eq!(description.tapes[1].iter().collect::<Vec<_>>(), vec![(0, 'c')]);
+        assert!(description.machine.alphabet.is_tape('c'));
+    }
+
+    #[test]
+    fn errors_point_at_the_word_that_caused_them(){
+        assert_eq!(error("q0 0 q1 1"), (1, 10, "expected 5 fields: state read next_state write move, found 4".to_string()));
+        assert_eq!(error("q0 0 q1 1 R extra"), (1, 13, "expected 5 fields: state read next_state write move".to_string()));
+        assert_eq!(error("\n  q0 0 q1 1 X"), (2, 13, "unknown move 'X', use L, R or S".to_string()));
+        assert_eq!(error("q0 0 q1 1 RX"), (1, 12, "unknown move 'X', use L, R or S".to_string()));
+        assert_eq!(error("q0 0 q1 11 R"), (1, 9, "expected 1 write symbols, one per tape, found 2".to_string()));
+        assert_eq!(error("q0 0 q1 1 R\nq0 01 q1 11 RR"), (2, 4, "expected 1 symbols, one per tape, as set on line 1".to_string()));
+        assert_eq!(error("bogus: 1"), (1, 1, "unknown directive \"bogus:\"".to_string()));
+        assert_eq!(error("start: a\nstart: b"), (2, 1, "start: was already given on line 1".to_string()));
+        assert_eq!(error("start: a b"), (1, 10, "start: takes a single value".to_string()));
+        assert_eq!(error("blank: ab"), (1, 8, "the blank must be a single character".to_string()));
+        assert_eq!(error("tapes: 0"), (1, 8, "\"0\" isn't a number of tapes".to_string()));
+        assert_eq!(error("initial: @x 01"), (1, 10, "\"@x\" isn't an offset like @-2".to_string()));
+        assert_eq!(error("tapes: 1\ninitial: 0\ninitial: 1"), (3, 10, "there are only 1 tapes to fill".to_string()));
+    }
+
+    #[test]
+    fn states_and_symbols_are_checked_once_everything_is_read(){
+        assert_eq!(error("states: q0 accept\nq0 0 q1 0 R"), (2, 6, "\"q1\" isn't one of the states given in states:".to_string()));
+        assert_eq!(error("input: 01\nq0 0 q0 2 R"), (2, 9, "'2' isn't in the tape alphabet".to_string()));
+        assert_eq!(error("input: 01\ninitial: 0a1"), (2, 11, "'a' isn't in the tape alphabet".to_string()));
+        assert_eq!(error("input: 0_1"), (1, 8, "the blank '_' can't be an input symbol".to_string()));
+        assert_eq!(error("blank: 0"), (1, 8, "the blank '0' can't be an input symbol".to_string()));
+    }
+
+    #[test]
+    fn the_alphabet_can_be_given_by_tape_alone(){
+        let description = Description::parse("tape: xy\nq0 x accept x R").unwrap();
+        assert!(description.declares_alphabet);
+        let alphabet = &description.machine.alphabet;
+        assert_eq!(alphabet.tape().collect::<String>(), "01_xy");
+        assert!(!alphabet.is_input('x'));
+        assert_eq!(error("tape: xy\nq0 z accept z R"), (2, 4, "'z' isn't in the tape alphabet".to_string()));
+    }
+
+    #[test]
+    fn columns_count_characters_not_bytes(){
+        assert_eq!(error("é ü q1 1 Q"), (1, 10, "unknown move 'Q', use L, R or S".to_string()));
+    }
+}
@@ -0,0 +1,345 @@
+//! drawing a machine's state diagram.
+//!
+//! states are nodes and each pair of states has one edge, labelled with every transition
+//! between them as `read→write,move`, one line each. on a machine with several tapes the
+//! reads, writes and moves have one character per tape, like `ab→ba,RL`.
+//! the start state has an arrow coming in, the accept state a double circle and the
+//! reject state a red one.
+//!
+//! [`dot`] writes a Graphviz document for tools that lay graphs out themselves and
+//! [`svg`] draws the diagram directly, with states where the machine's layout puts them,
+//! so nothing else has to be installed.
+
+use std::{collections::{BTreeMap, HashSet}, fmt::Write};
+
+use crate::{import::unused_state, jflap::escape, machine::{Machine, Move, Point, Transition}};
+
+const ARROW: char = '→';
+const ACCEPT_COLOR: &str = "#2e7d32";
+const REJECT_COLOR: &str = "#c62828";
+const STATE_COLOR: &str = "#000000";
+/// node the arrow into the start state comes from in DOT
+const START_NODE: &str = "__start";
+//svg
+pub const STATE_RADIUS: f32 = 28.0;
+/// gap between the two circles of the accept state
+pub const ACCEPT_RING_GAP: f32 = 5.0;
+pub const START_ARROW_LENGTH: f32 = 40.0;
+/// how far edges between states going both ways bend apart
+const EDGE_BEND: f32 = 40.0;
+pub const FONT_SIZE: f32 = 14.0;
+pub const LINE_HEIGHT: f32 = FONT_SIZE * 1.2;
+/// space around the diagram for labels
+const MARGIN: f32 = 100.0;
+
+fn move_letter(movement: Move) -> char{
+    match movement{
+        Move::Left => 'L',
+        Move::Right => 'R',
+        Move::Stay => 'S',
+    }
+}
+
+/// a transition's line on its edge, like `0→1,R`
+fn label(read: &[char], transition: &Transition) -> String{
+    let read: String = read.iter().collect();
+    let write: String = transition.write.iter().collect();
+    let movement: String = transition.movement.iter().copied().map(move_letter).collect();
+    format!("{}{}{},{}", read, ARROW, write, movement)
+}
+
+/// the label lines of every edge, keyed by the states it goes from and to
+fn edges(machine: &Machine) -> BTreeMap<(&str, &str), Vec<String>>{
+    let mut transitions: Vec<_> = machine.transitions.iter().collect();
+    transitions.sort_by(|a, b| a.0.cmp(b.0));
+    let mut edges: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+    for ((state, read), choices) in transitions{
+        for transition in choices{
+            edges.entry((state.as_str(), transition.next_state.as_str())).or_default().push(label(read, transition));
+        }
+    }
+    edges
+}
+
+/// `text` as a quoted DOT string
+fn quote(text: &str) -> String{
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// writes the machine's state diagram as a Graphviz DOT document called `name`
+pub fn dot(machine: &Machine, name: &str) -> String{
+    let states = machine.states();
+    let used: HashSet<String> = states.iter().map(|state| state.to_string()).collect();
+    let start_node = unused_state(START_NODE, &used);
+
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph {} {{", quote(name));
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=circle];\n");
+    let _ = writeln!(dot, "    {} [shape=none, label=\"\", width=0, height=0];", quote(&start_node));
+    for state in &states{
+        let style = if *state == machine.accept_state{
+            format!(" [shape=doublecircle, color={}]", quote(ACCEPT_COLOR))
+        }else if *state == machine.reject_state{
+            format!(" [color={}]", quote(REJECT_COLOR))
+        }else{
+            String::new()
+        };
+        let _ = writeln!(dot, "    {}{};", quote(state), style);
+    }
+    let _ = writeln!(dot, "    {} -> {};", quote(&start_node), quote(&machine.start_state));
+    for ((from, to), lines) in edges(machine){
+        let _ = writeln!(dot, "    {} -> {} [label={}];", quote(from), quote(to), quote(&lines.join("\n")));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn add(a: Point, b: Point) -> Point{
+    Point{x: a.x + b.x, y: a.y + b.y}
+}
+
+fn scale(a: Point, factor: f32) -> Point{
+    Point{x: a.x * factor, y: a.y * factor}
+}
+
+/// the unit vector from `from` towards `to`
+fn direction(from: Point, to: Point) -> Point{
+    let (x, y) = (to.x - from.x, to.y - from.y);
+    let length = (x * x + y * y).sqrt().max(f32::EPSILON);
+    Point{x: x / length, y: y / length}
+}
+
+/// how a label lines up with its point, like svg's `text-anchor`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor{
+    Start,
+    Middle,
+    End,
+}
+
+impl Anchor{
+    fn svg(self) -> &'static str{
+        match self{
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        }
+    }
+}
+
+/// the line an edge is drawn along, from the rim of one state to the rim of the other
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve{
+    Quadratic{start: Point, control: Point, end: Point},
+    Cubic{start: Point, first: Point, second: Point, end: Point},
+}
+
+impl Curve{
+    /// the point `t` of the way along, from 0 at the start to 1 at the end
+    pub fn point(&self, t: f32) -> Point{
+        let u = 1.0 - t;
+        match *self{
+            Curve::Quadratic{start, control, end} => add(add(scale(start, u * u), scale(control, 2.0 * u * t)), scale(end, t * t)),
+            Curve::Cubic{start, first, second, end} => add(
+                add(scale(start, u * u * u), scale(first, 3.0 * u * u * t)),
+                add(scale(second, 3.0 * u * t * t), scale(end, t * t * t)),
+            ),
+        }
+    }
+
+    /// the curve as svg path data
+    fn svg(&self) -> String{
+        match self{
+            Curve::Quadratic{start, control, end} => format!("M{:.1},{:.1} Q{:.1},{:.1} {:.1},{:.1}", start.x, start.y, control.x, control.y, end.x, end.y),
+            Curve::Cubic{start, first, second, end} => format!("M{:.1},{:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}", start.x, start.y, first.x, first.y, second.x, second.y, end.x, end.y),
+        }
+    }
+}
+
+/// an arrow between two states with every transition it stands for
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge{
+    pub from: String,
+    pub to: String,
+    /// one per transition, like `0→1,R`
+    pub lines: Vec<String>,
+    pub curve: Curve,
+    /// where the label's last line sits
+    pub label: Point,
+    pub anchor: Anchor,
+}
+
+/// where everything in a state diagram is drawn, with y growing downwards
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout{
+    pub states: BTreeMap<String, Point>,
+    pub edges: Vec<Edge>,
+    /// top left and bottom right corners of everything drawn, labels included
+    pub bounds: (Point, Point),
+}
+
+impl Layout{
+    /// the state whose circle `point` is in
+    pub fn state_at(&self, point: Point) -> Option<&str>{
+        self.states.iter()
+            .find(|(_, center)| (center.x - point.x).hypot(center.y - point.y) <= STATE_RADIUS)
+            .map(|(state, _)| state.as_str())
+    }
+
+    /// the edge passing within `distance` of `point`
+    pub fn edge_at(&self, point: Point, distance: f32) -> Option<&Edge>{
+        const SAMPLES: usize = 32;
+        self.edges.iter().find(|edge| (0..=SAMPLES).any(|i| {
+            let on_curve = edge.curve.point(i as f32 / SAMPLES as f32);
+            (on_curve.x - point.x).hypot(on_curve.y - point.y) <= distance
+        }))
+    }
+}
+
+/// works out where the machine's states, edges and labels go. states sit where
+/// [`Machine::positions`] puts them
+pub fn layout(machine: &Machine) -> Layout{
+    let positions = machine.positions();
+    let labels = edges(machine);
+    let (mut left, mut top, mut right, mut bottom) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for point in positions.values(){
+        left = left.min(point.x);
+        top = top.min(point.y);
+        right = right.max(point.x);
+        bottom = bottom.max(point.y);
+    }
+    //the tallest self loop label has to fit above its state
+    let tallest = labels.values().map(Vec::len).max().unwrap_or(0) as f32 * LINE_HEIGHT;
+    let bounds = (Point{x: left - MARGIN - START_ARROW_LENGTH, y: top - MARGIN - tallest}, Point{x: right + MARGIN, y: bottom + MARGIN});
+
+    let mut edges = Vec::new();
+    for (&(from, to), lines) in &labels{
+        let (start, end) = (positions[from], positions[to]);
+        let (curve, label, anchor) = if from == to{
+            let side = STATE_RADIUS * 0.6;
+            let rise = STATE_RADIUS * 0.8;
+            let reach = STATE_RADIUS * 2.8;
+            let curve = Curve::Cubic{
+                start: Point{x: start.x - side, y: start.y - rise},
+                first: Point{x: start.x - side * 2.5, y: start.y - reach},
+                second: Point{x: start.x + side * 2.5, y: start.y - reach},
+                end: Point{x: start.x + side, y: start.y - rise},
+            };
+            (curve, Point{x: start.x, y: start.y - STATE_RADIUS * 2.5}, Anchor::Middle)
+        }else{
+            let along = direction(start, end);
+            //edges both ways bend to opposite sides so they don't overlap
+            let bend = if labels.contains_key(&(to, from)) {EDGE_BEND} else {0.0};
+            let normal = Point{x: along.y, y: -along.x};
+            let control = add(scale(add(start, end), 0.5), scale(normal, bend));
+            let from_edge = add(start, scale(direction(start, control), STATE_RADIUS));
+            let to_edge = add(end, scale(direction(end, control), STATE_RADIUS));
+            let middle = add(scale(add(from_edge, to_edge), 0.25), scale(control, 0.5));
+            //labels sit on the outside of the bend, or above a straight edge
+            let outside = if bend == 0.0 && normal.y > 0.0 {scale(normal, -1.0)} else {normal};
+            let lift = if outside.y > 0.0 {LINE_HEIGHT * lines.len() as f32} else {FONT_SIZE * 0.5};
+            //beside an edge going up or down, the label starts or ends next to it rather than across it
+            let anchor = if outside.x > 0.5 {Anchor::Start} else if outside.x < -0.5 {Anchor::End} else {Anchor::Middle};
+            let label = Point{x: middle.x + outside.x * FONT_SIZE * 0.5, y: middle.y + outside.y * lift - FONT_SIZE * 0.3};
+            (Curve::Quadratic{start: from_edge, control, end: to_edge}, label, anchor)
+        };
+        edges.push(Edge{from: from.to_string(), to: to.to_string(), lines: lines.clone(), curve, label, anchor});
+    }
+
+    Layout{
+        states: positions.into_iter().map(|(state, point)| (state.to_string(), point)).collect(),
+        edges,
+        bounds,
+    }
+}
+
+/// lines of text lined up on `x` by `anchor`, the last one sitting on `bottom`
+fn text(svg: &mut String, x: f32, bottom: f32, anchor: Anchor, lines: &[String]){
+    let top = bottom - LINE_HEIGHT * (lines.len() - 1) as f32;
+    let _ = write!(svg, "  <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\" font-family=\"monospace\" font-size=\"{}\">", x, top, anchor.svg(), FONT_SIZE);
+    for (i, line) in lines.iter().enumerate(){
+        let dy = if i == 0 {0.0} else {LINE_HEIGHT};
+        let _ = write!(svg, "<tspan x=\"{:.1}\" dy=\"{:.1}\">{}</tspan>", x, dy, escape(line));
+    }
+    svg.push_str("</text>\n");
+}
+
+/// draws the machine's state diagram as an SVG image
+pub fn svg(machine: &Machine) -> String{
+    let layout = layout(machine);
+    let (top_left, bottom_right) = layout.bounds;
+    let (left, top) = (top_left.x, top_left.y);
+    let (width, height) = (bottom_right.x - left, bottom_right.y - top);
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{:.1} {:.1} {:.1} {:.1}\" width=\"{:.0}\" height=\"{:.0}\">", left, top, width, height, width, height);
+    svg.push_str("  <defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\"/></marker></defs>\n");
+    let _ = writeln!(svg, "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"white\"/>", left, top, width, height);
+
+    //edges first so states are drawn over their ends
+    for edge in &layout.edges{
+        let _ = writeln!(svg, "  <path d=\"{}\" fill=\"none\" stroke=\"{}\" marker-end=\"url(#arrow)\"/>", edge.curve.svg(), STATE_COLOR);
+        text(&mut svg, edge.label.x, edge.label.y, edge.anchor, &edge.lines);
+    }
+
+    let start = layout.states[&machine.start_state];
+    let _ = writeln!(svg, "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" marker-end=\"url(#arrow)\"/>",
+        start.x - STATE_RADIUS - START_ARROW_LENGTH, start.y, start.x - STATE_RADIUS, start.y, STATE_COLOR);
+    for (state, point) in &layout.states{
+        let color = if *state == machine.accept_state {ACCEPT_COLOR} else if *state == machine.reject_state {REJECT_COLOR} else {STATE_COLOR};
+        let _ = writeln!(svg, "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"white\" stroke=\"{}\" stroke-width=\"2\"/>", point.x, point.y, STATE_RADIUS, color);
+        if *state == machine.accept_state{
+            let _ = writeln!(svg, "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>", point.x, point.y, STATE_RADIUS - ACCEPT_RING_GAP, color);
+        }
+        text(&mut svg, point.x, point.y + FONT_SIZE * 0.35, Anchor::Middle, std::slice::from_ref(state));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn writes_dot(){
+        let machine = Machine::parse("q0 0 q0 1 R\nq0 1 q0 1 R\nq0 _ accept _ S\nq0 x reject x S").unwrap();
+        assert_eq!(dot(&machine, "binary \"ones\""), concat!(
+            "digraph \"binary \\\"ones\\\"\" {\n",
+            "    rankdir=LR;\n",
+            "    node [shape=circle];\n",
+            "    \"__start\" [shape=none, label=\"\", width=0, height=0];\n",
+            "    \"accept\" [shape=doublecircle, color=\"#2e7d32\"];\n",
+            "    \"q0\";\n",
+            "    \"reject\" [color=\"#c62828\"];\n",
+            "    \"__start\" -> \"q0\";\n",
+            "    \"q0\" -> \"accept\" [label=\"_→_,S\"];\n",
+            "    \"q0\" -> \"q0\" [label=\"0→1,R\\n1→1,R\"];\n",
+            "    \"q0\" -> \"reject\" [label=\"x→x,S\"];\n",
+            "}\n",
+        ));
+    }
+
+    #[test]
+    fn the_start_node_never_clashes_with_a_state(){
+        let machine = Machine::parse("__start 0 accept 0 S\nstart: __start").unwrap();
+        let dot = dot(&machine, "m");
+        assert!(dot.contains("\"__start2\" -> \"__start\";"));
+    }
+
+    #[test]
+    fn finds_states_and_edges_under_a_point(){
+        let mut machine = Machine::parse("q0 0 q1 0 R").unwrap();
+        machine.layout.insert("q0".to_string(), Point{x: 0.0, y: 0.0});
+        machine.layout.insert("q1".to_string(), Point{x: 200.0, y: 0.0});
+        machine.layout.insert("accept".to_string(), Point{x: 0.0, y: 200.0});
+        let layout = layout(&machine);
+        assert_eq!(layout.state_at(Point{x: 10.0, y: 10.0}), Some("q0"));
+        assert_eq!(layout.state_at(Point{x: 100.0, y: 0.0}), None);
+        let edge = layout.edge_at(Point{x: 100.0, y: 2.0}, 5.0).expect("the edge runs between the states");
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("q0", "q1"));
+        assert_eq!(edge.lines, vec!["0→0,R".to_string()]);
+        assert!(layout.edge_at(Point{x: 100.0, y: 100.0}, 5.0).is_none());
+    }
+}
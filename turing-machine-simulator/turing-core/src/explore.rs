@@ -0,0 +1,196 @@
+//! breadth-first exploration of a nondeterministic machine's computations.
+//!
+//! every configuration the machine can reach is a node of the computation tree, with one child
+//! per choice it can take. exploring stops at the first accepting configuration, so the one found
+//! takes as few steps as possible, or once the tree holds a set number of configurations.
+
+use std::collections::VecDeque;
+
+use crate::{machine::Status, simulation::{apply, Track}, Simulation};
+
+/// number of configurations explored unless told otherwise
+pub const DEFAULT_EXPLORATION_LIMIT: usize = 10_000;
+
+/// a configuration reached while exploring
+#[derive(Clone, Debug)]
+pub struct Node{
+    pub parent: Option<usize>,
+    /// index of the choice the parent took to get here
+    pub choice: usize,
+    pub children: Vec<usize>,
+    pub state: String,
+    pub status: Status,
+    /// steps taken since the configuration exploring started from
+    pub depth: u64,
+}
+
+/// what exploring found out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome{
+    /// some branch accepts, ending at this node
+    Accepted(usize),
+    /// every branch halts without accepting
+    Rejected,
+    /// the limit was reached before any branch accepted
+    Undecided,
+}
+
+/// the configurations reachable from a simulation, found breadth-first
+#[derive(Clone, Debug)]
+pub struct ComputationTree{
+    /// the root, where exploring started, is the first node
+    pub nodes: Vec<Node>,
+    pub outcome: Outcome,
+}
+
+impl ComputationTree{
+    /// explores from `simulation` until a branch accepts, every branch halts,
+    /// or `limit` configurations have been found
+    pub fn explore(simulation: &Simulation, limit: usize) -> Self{
+        //every configuration shares the machine, only the tapes and heads are copied
+        let machine = &simulation.machine;
+        let read = |tracks: &[Track]| tracks.iter().map(Track::read).collect::<Vec<_>>();
+        let status = machine.status(&simulation.state, &read(&simulation.tracks));
+        let mut tree = Self{
+            nodes: vec![Node{
+                parent: None,
+                choice: 0,
+                children: Vec::new(),
+                state: simulation.state.clone(),
+                status,
+                depth: 0,
+            }],
+            outcome: Outcome::Rejected,
+        };
+        match status{
+            Status::Accepted => {
+                tree.outcome = Outcome::Accepted(0);
+                return tree;
+            },
+            Status::Running => (),
+            _ => return tree,
+        }
+
+        //the node each configuration is, along with its tapes and heads
+        let mut frontier = VecDeque::from([(0, simulation.tracks.clone())]);
+        while let Some((parent, tracks)) = frontier.pop_front(){
+            let choices = machine.choices(&tree.nodes[parent].state, &read(&tracks));
+            for (choice, transition) in choices.iter().enumerate(){
+                if tree.nodes.len() >= limit{
+                    tree.outcome = Outcome::Undecided;
+                    return tree;
+                }
+                let mut child = tracks.clone();
+                apply(&mut child, transition);
+                let status = machine.status(&transition.next_state, &read(&child));
+                let index = tree.nodes.len();
+                tree.nodes.push(Node{
+                    parent: Some(parent),
+                    choice,
+                    children: Vec::new(),
+                    state: transition.next_state.clone(),
+                    status,
+                    depth: tree.nodes[parent].depth + 1,
+                });
+                tree.nodes[parent].children.push(index);
+                match status{
+                    Status::Accepted => {
+                        tree.outcome = Outcome::Accepted(index);
+                        return tree;
+                    },
+                    Status::Running => frontier.push_back((index, child)),
+                    _ => (),
+                }
+            }
+        }
+        tree
+    }
+
+    /// the choices taken from the root to reach `node`
+    pub fn path(&self, node: usize) -> Vec<usize>{
+        let mut path = Vec::new();
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent{
+            path.push(self.nodes[current].choice);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::{Machine, Tape};
+
+    fn simulation(program: &str, input: &str) -> Simulation{
+        Simulation::new(vec![Tape::from_cells(input)], Machine::parse(program).unwrap())
+    }
+
+    #[test]
+    fn finds_the_shortest_accepting_branch(){
+        //guesses where the 1 is, only the guess on it accepts
+        let program = "q0 0 q0 0 R\nq0 1 q0 1 R\nq0 1 check 1 S\ncheck 1 accept 1 S";
+        let tree = ComputationTree::explore(&simulation(program, "0011"), DEFAULT_EXPLORATION_LIMIT);
+        let Outcome::Accepted(node) = tree.outcome else{
+            panic!("expected to accept, found {:?}", tree.outcome);
+        };
+        assert_eq!(tree.nodes[node].state, "accept");
+        assert_eq!(tree.nodes[node].depth, 4);
+        assert_eq!(tree.path(node), vec![0, 0, 1, 0]);
+
+        let mut replay = simulation(program, "0011");
+        for choice in tree.path(node){
+            replay.step_choice(choice);
+        }
+        assert_eq!(replay.status, Status::Accepted);
+        assert_eq!(replay.tracks[0].head, 2);
+    }
+
+    #[test]
+    fn rejects_when_every_branch_halts(){
+        let program = "q0 0 a 0 R\nq0 0 b 0 R\na _ reject _ S";
+        let tree = ComputationTree::explore(&simulation(program, "0"), DEFAULT_EXPLORATION_LIMIT);
+        assert_eq!(tree.outcome, Outcome::Rejected);
+        assert_eq!(tree.nodes.len(), 4);
+        assert_eq!(tree.nodes[0].children, vec![1, 2]);
+        assert_eq!(tree.nodes[1].status, Status::Running);
+        assert_eq!(tree.nodes[2].status, Status::Stuck);
+        assert_eq!(tree.nodes[3].status, Status::Rejected);
+    }
+
+    #[test]
+    fn stops_at_the_limit(){
+        let program = "q0 _ q0 _ R\nq0 _ q0 _ L";
+        let tree = ComputationTree::explore(&simulation(program, ""), 50);
+        assert_eq!(tree.outcome, Outcome::Undecided);
+        assert_eq!(tree.nodes.len(), 50);
+    }
+
+    #[test]
+    fn explores_machines_with_several_tapes(){
+        //copies the input onto the second tape, or gives up at any point
+        let program = "q0 0_ q0 00 RR\nq0 1_ q0 11 RR\nq0 0_ reject 0_ SS\nq0 __ accept __ SS";
+        let start = simulation(program, "01");
+        assert_eq!(start.tracks.len(), 2);
+        let tree = ComputationTree::explore(&start, DEFAULT_EXPLORATION_LIMIT);
+        let Outcome::Accepted(node) = tree.outcome else{
+            panic!("expected to accept, found {:?}", tree.outcome);
+        };
+        assert_eq!(tree.nodes[node].depth, 3);
+        assert_eq!(tree.nodes[0].children.len(), 2);
+        assert_eq!(tree.nodes[tree.nodes[0].children[1]].status, Status::Rejected);
+        //the simulation explored from is left alone
+        assert_eq!(start.steps, 0);
+    }
+
+    #[test]
+    fn a_halted_root_is_the_whole_tree(){
+        let mut halted = simulation("q0 _ accept _ S", "");
+        halted.step();
+        let tree = ComputationTree::explore(&halted, DEFAULT_EXPLORATION_LIMIT);
+        assert_eq!(tree.outcome, Outcome::Accepted(0));
+        assert_eq!(tree.nodes.len(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+//! what machines converted from other simulators have in common.
+//!
+//! other simulators can have several accept states and no reject state. a converted machine
+//! accepts in the first of them and the rest take one extra step to get there.
+
+use std::{collections::HashSet, fmt};
+
+use crate::{description::{Description, Position}, machine::{Machine, Move, Transition}};
+
+/// transitions added to join extra accept states to the first before giving up
+const MAX_JOINING_TRANSITIONS: usize = 4096;
+
+/// something in an imported file that changed meaning or was left out
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning{
+    pub position: Position,
+    pub message: String,
+}
+
+impl fmt::Display for Warning{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{}:{}: {}", self.position.line, self.position.column, self.message)
+    }
+}
+
+/// a converted machine
+#[derive(Clone, Debug)]
+pub struct Import{
+    /// what the file calls the machine, if it does
+    pub name: Option<String>,
+    pub description: Description,
+    pub warnings: Vec<Warning>,
+}
+
+/// a state name based on `base` that isn't in `used`
+pub fn unused_state(base: &str, used: &HashSet<String>) -> String{
+    std::iter::once(base.to_string())
+        .chain((2..).map(|n| format!("{}{}", base, n)))
+        .find(|state| !used.contains(state))
+        .expect("there is always an unused state")
+}
+
+/// makes every extra accept state go on to the accept state whatever it reads, without changing the tapes.
+/// their own transitions are dropped, since the other simulator halted as soon as it got to them,
+/// and whether there were any is returned
+pub(crate) fn join_accept_states(machine: &mut Machine, extra: &[String]) -> Result<bool, String>{
+    let before = machine.transitions.len();
+    machine.transitions.retain(|(state, _), _| !extra.contains(state));
+    let dropped = machine.transitions.len() != before;
+
+    let symbols: Vec<char> = machine.alphabet.tape().collect();
+    let combinations = symbols.len().checked_pow(machine.tape_count as u32).filter(|&count| count.checked_mul(extra.len()).is_some_and(|n| n <= MAX_JOINING_TRANSITIONS))
+        .ok_or("there are too many symbols and tapes to join the accept states into one")?;
+    for state in extra{
+        for combination in 0..combinations{
+            let read: Vec<char> = (0..machine.tape_count)
+                .map(|tape| symbols[combination / symbols.len().pow(tape as u32) % symbols.len()])
+                .collect();
+            let transition = Transition{
+                next_state: machine.accept_state.clone(),
+                write: read.clone(),
+                movement: vec![Move::Stay; machine.tape_count],
+            };
+            machine.add_transition(state.clone(), read, transition);
+        }
+    }
+    Ok(dropped)
+}
+
+/// the warnings about joining `extra` to `accept_state`
+pub(crate) fn joining_warnings(accept_state: &str, extra: &[String], dropped: bool, position: Position) -> Vec<Warning>{
+    let mut warnings = Vec::new();
+    if dropped{
+        warnings.push(Warning{position, message: "transitions out of accept states were left out".to_string()});
+    }
+    warnings.push(Warning{
+        position,
+        message: format!("only one state can accept, so there is an extra step from {} to {}", extra.join(", "), accept_state),
+    });
+    warnings
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn joining_too_many_combinations_is_an_error(){
+        //2 symbols on 62 tapes fits in a usize, but not once for each of the extra states
+        let mut machine = Machine{tape_count: 62, ..Default::default()};
+        machine.alphabet = crate::Alphabet::new(['1'], [], '_').unwrap();
+        let extra: Vec<String> = (0..4).map(|i| format!("f{}", i)).collect();
+        assert!(join_accept_states(&mut machine, &extra).is_err());
+    }
+}
@@ -0,0 +1,446 @@
+//! reading and writing JFLAP's `.jff` files for turing machines.
+//!
+//! a `.jff` file is XML. a turing machine's states are `block` (or, in older files, `state`)
+//! elements with an id, a name, a position and `initial` or `final` markers, and each transition
+//! gives the ids it goes between along with what it reads, writes and how it moves:
+//!
+//! ```text
+//! <structure>
+//!     <type>turing</type>
+//!     <automaton>
+//!         <block id="0" name="q0"><x>60.0</x><y>100.0</y><initial/></block>
+//!         <block id="1" name="q1"><x>200.0</x><y>100.0</y><final/></block>
+//!         <transition><from>0</from><to>1</to><read/><write>1</write><move>R</move></transition>
+//!     </automaton>
+//! </structure>
+//! ```
+//!
+//! an empty `read` or `write` is the blank and moves are `L`, `R` and `S`. machines with more
+//! than one tape say how many in a `tapes` element, and give every `read`, `write` and `move`
+//! a `tape` attribute counting from 1. a `~` read matches any symbol and a `~` write leaves the
+//! symbol as it is; they are expanded into a transition for each symbol the machine uses,
+//! alongside any others for the same symbol, just as JFLAP tries them all.
+//! since empty stands for the blank, machines with their own blank are read back with `_`, and
+//! machines that use `~` as a symbol, or `_` besides their own blank, can't be exported.
+//!
+//! JFLAP halts without accepting when nothing can be done, so the reject state is exported as
+//! a plain state, which machines with transitions out of it can't be exported with, and imported
+//! machines get a reject state nothing goes to.
+//! several final states are handled like the [other imports](crate::import).
+
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt::Write};
+
+use roxmltree::{Document, Node};
+
+use crate::{alphabet::Alphabet, description::{Description, ParseError, Position}, import::{join_accept_states, joining_warnings, unused_state, Import, Warning}, machine::{Machine, Move, Point, Transition, DEFAULT_ACCEPT_STATE, DEFAULT_REJECT_STATE}, tape::{Tape, BLANK}};
+
+const TURING_TYPE: &str = "turing";
+/// matches any symbol when read and keeps the symbol when written
+const ANY_SYMBOL: char = '~';
+
+/// a transition as written in the file, with `None` for `~`
+struct RawTransition{
+    from: String,
+    to: String,
+    read: Vec<Option<char>>,
+    write: Vec<Option<char>>,
+    movement: Vec<Move>,
+    position: Position,
+}
+
+/// where `node` starts in the file
+fn position(document: &Document, node: Node) -> Position{
+    let position = document.text_pos_at(node.range().start);
+    Position{line: position.row as usize, column: position.col as usize}
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>>{
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+/// the text of `node`'s child called `name`
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str>{
+    child(node, name).map(|child| child.text().unwrap_or("").trim())
+}
+
+/// the symbol in a `read` or `write` element, where empty is the blank
+fn symbol(document: &Document, node: Node) -> Result<Option<char>, ParseError>{
+    let text = node.text().unwrap_or("");
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()){
+        (None, _) => Ok(Some(BLANK)),
+        (Some(ANY_SYMBOL), None) => Ok(None),
+        (Some(c), None) => Ok(Some(c)),
+        _ => Err(ParseError::new(position(document, node), format!("\"{}\" isn't a single symbol", text))),
+    }
+}
+
+/// the `read`, `write` or `move` elements of a transition, in tape order
+fn per_tape<'a, 'input>(document: &Document, transition: Node<'a, 'input>, name: &str, tape_count: usize) -> Result<Vec<Node<'a, 'input>>, ParseError>{
+    let mut nodes = vec![None; tape_count];
+    for node in transition.children().filter(|child| child.has_tag_name(name)){
+        let tape = match node.attribute("tape"){
+            None if tape_count == 1 => 1,
+            None => return Err(ParseError::new(position(document, node), format!("<{}> needs a tape attribute on a machine with {} tapes", name, tape_count))),
+            Some(tape) => tape.parse().ok().filter(|tape| (1..=tape_count).contains(tape))
+                .ok_or_else(|| ParseError::new(position(document, node), format!("\"{}\" isn't a tape from 1 to {}", tape, tape_count)))?,
+        };
+        if nodes[tape - 1].replace(node).is_some(){
+            return Err(ParseError::new(position(document, node), format!("<{}> is given twice for tape {}", name, tape)));
+        }
+    }
+    nodes.into_iter().enumerate()
+        .map(|(tape, node)| node.ok_or_else(|| ParseError::new(position(document, transition), format!("the transition has no <{}> for tape {}", name, tape + 1))))
+        .collect()
+}
+
+/// reads a JFLAP turing machine
+pub fn import(text: &str) -> Result<Import, ParseError>{
+    let document = Document::parse(text).map_err(|e| {
+        let position = e.pos();
+        ParseError::new(Position{line: position.row as usize, column: position.col as usize}, e.to_string())
+    })?;
+    let structure = document.root_element();
+    let kind = child_text(structure, "type").unwrap_or("");
+    if kind != TURING_TYPE{
+        let message = match kind{
+            "turingbb" => "building blocks aren't supported".to_string(),
+            "" => "the file doesn't say what kind of automaton it is".to_string(),
+            kind => format!("this is a {} automaton, not a turing machine", kind),
+        };
+        return Err(ParseError::new(position(&document, structure), message));
+    }
+    let tape_count = match child(structure, "tapes"){
+        None => 1,
+        Some(tapes) => tapes.text().and_then(|text| text.trim().parse().ok()).filter(|&count| count > 0)
+            .ok_or_else(|| ParseError::new(position(&document, tapes), "<tapes> needs a number above 0"))?,
+    };
+    let automaton = child(structure, "automaton")
+        .ok_or_else(|| ParseError::new(position(&document, structure), "there is no <automaton>"))?;
+
+    let mut warnings = Vec::new();
+    let mut machine = Machine{tape_count, ..Machine::default()};
+    let mut names: HashMap<&str, String> = HashMap::new();
+    let mut used_states = HashSet::new();
+    let mut start_state = None;
+    let mut accept_states: Vec<(String, Position)> = Vec::new();
+    for state in automaton.children().filter(|child| child.has_tag_name("block") || child.has_tag_name("state")){
+        let at = position(&document, state);
+        let id = state.attribute("id").ok_or_else(|| ParseError::new(at, "a state has no id"))?;
+        if child(state, "automaton").is_some(){
+            return Err(ParseError::new(at, "building blocks aren't supported"));
+        }
+        let mut name = state.attribute("name").unwrap_or(id).to_string();
+        if used_states.contains(&name){
+            let renamed = unused_state(&format!("{}_{}", name, id), &used_states);
+            warnings.push(Warning{position: at, message: format!("there is already a state called {}, so this one is called {}", name, renamed)});
+            name = renamed;
+        }
+        if names.insert(id, name.clone()).is_some(){
+            return Err(ParseError::new(at, format!("there is already a state with id {}", id)));
+        }
+        used_states.insert(name.clone());
+
+        let coordinate = |axis| child_text(state, axis).and_then(|text| text.parse::<f32>().ok());
+        if let (Some(x), Some(y)) = (coordinate("x"), coordinate("y")){
+            machine.layout.insert(name.clone(), Point{x, y});
+        }
+        if child(state, "initial").is_some(){
+            if let Some((first, _)) = &start_state{
+                return Err(ParseError::new(at, format!("{} is already the initial state", first)));
+            }
+            start_state = Some((name.clone(), at));
+        }
+        if child(state, "final").is_some(){
+            accept_states.push((name, at));
+        }
+    }
+    let Some((start_state, _)) = start_state else{
+        return Err(ParseError::new(position(&document, automaton), "no state is initial"));
+    };
+    machine.start_state = start_state;
+
+    let mut transitions = Vec::new();
+    for transition in automaton.children().filter(|child| child.has_tag_name("transition")){
+        let at = position(&document, transition);
+        let state = |end| -> Result<String, ParseError>{
+            let id = child_text(transition, end).ok_or_else(|| ParseError::new(at, format!("the transition has no <{}>", end)))?;
+            names.get(id).cloned().ok_or_else(|| ParseError::new(at, format!("there is no state with id {}", id)))
+        };
+        let (from, to) = (state("from")?, state("to")?);
+        let read = per_tape(&document, transition, "read", tape_count)?.into_iter().map(|node| symbol(&document, node)).collect::<Result<Vec<_>, _>>()?;
+        let write = per_tape(&document, transition, "write", tape_count)?.into_iter().map(|node| symbol(&document, node)).collect::<Result<Vec<_>, _>>()?;
+        let movement = per_tape(&document, transition, "move", tape_count)?.into_iter()
+            .map(|node| match node.text().unwrap_or("").trim(){
+                "L" => Ok(Move::Left),
+                "R" => Ok(Move::Right),
+                "S" => Ok(Move::Stay),
+                other => Err(ParseError::new(position(&document, node), format!("\"{}\" isn't a move, use L, R or S", other))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        transitions.push(RawTransition{from, to, read, write, movement, position: at});
+    }
+
+    //symbols the wildcards stand for
+    for transition in &transitions{
+        let written = transition.read.iter().zip(&transition.write).map(|(read, write)| write.or(*read).unwrap_or(BLANK)).collect();
+        let read = transition.read.iter().map(|read| read.unwrap_or(BLANK)).collect();
+        machine.add_transition(transition.from.clone(), read, Transition{next_state: transition.to.clone(), write: written, movement: transition.movement.clone()});
+    }
+    let tapes = vec![Tape::new(BLANK); tape_count];
+    let alphabet = Alphabet::covering(&tapes, &machine);
+    let symbols: Vec<char> = alphabet.tape().collect();
+
+    machine.transitions.clear();
+    for transition in transitions{
+        if transition.read.contains(&None){
+            warnings.push(Warning{position: transition.position, message: format!("{} matches any symbol, so it was turned into a transition for each of {}", ANY_SYMBOL, symbols.iter().collect::<String>())});
+        }
+        for read in expand(&transition.read, &symbols){
+            let write = read.iter().zip(&transition.write).map(|(&read, write)| write.unwrap_or(read)).collect();
+            machine.add_transition(transition.from.clone(), read, Transition{next_state: transition.to.clone(), write, movement: transition.movement.clone()});
+        }
+    }
+    machine.alphabet = alphabet;
+
+    used_states.extend(accept_states.iter().map(|(state, _)| state.clone()));
+    machine.reject_state = unused_state(DEFAULT_REJECT_STATE, &used_states);
+    match accept_states.split_first(){
+        None => machine.accept_state = unused_state(DEFAULT_ACCEPT_STATE, &used_states),
+        Some(((accept_state, _), extra)) => {
+            machine.accept_state = accept_state.clone();
+            if let Some((_, position)) = extra.first(){
+                let extra: Vec<String> = extra.iter().map(|(state, _)| state.clone()).collect();
+                let dropped = join_accept_states(&mut machine, &extra).map_err(|e| ParseError::new(*position, e))?;
+                warnings.extend(joining_warnings(accept_state, &extra, dropped, *position));
+            }
+        },
+    }
+
+    Ok(Import{
+        name: None,
+        description: Description{
+            machine,
+            declares_alphabet: false,
+            tapes,
+        },
+        warnings,
+    })
+}
+
+/// every combination of symbols matching `read`, where `None` matches any of `symbols`
+fn expand(read: &[Option<char>], symbols: &[char]) -> Vec<Vec<char>>{
+    let mut combinations = vec![Vec::new()];
+    for symbol in read{
+        let options = match symbol{
+            Some(symbol) => vec![*symbol],
+            None => symbols.to_vec(),
+        };
+        combinations = combinations.into_iter()
+            .flat_map(|combination| options.iter().map(move |&option| {
+                let mut combination = combination.clone();
+                combination.push(option);
+                combination
+            }))
+            .collect();
+    }
+    combinations
+}
+
+/// `text` with the characters XML gives meaning to escaped
+pub(crate) fn escape(text: &str) -> String{
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// a `read`, `write` or `move` element
+fn tape_element(xml: &mut String, name: &str, tape: Option<usize>, text: &str){
+    let tape = tape.map_or(String::new(), |tape| format!(" tape=\"{}\"", tape));
+    if text.is_empty(){
+        let _ = writeln!(xml, "\t\t\t<{}{}/>", name, tape);
+    }else{
+        let _ = writeln!(xml, "\t\t\t<{0}{1}>{2}</{0}>", name, tape, escape(text));
+    }
+}
+
+/// a machine written as a JFLAP file
+#[derive(Clone, Debug)]
+pub struct Export{
+    pub xml: String,
+    /// what will read back differently
+    pub warnings: Vec<String>,
+}
+
+/// writes `machine` as a JFLAP turing machine. states are placed where the layout puts them.
+///
+/// fails if the machine uses a symbol JFLAP gives another meaning to
+pub fn export(machine: &Machine) -> Result<Export, String>{
+    let blank = machine.alphabet.blank();
+    let mut warnings = Vec::new();
+    if blank != BLANK{
+        if machine.alphabet.is_tape(BLANK){
+            return Err(format!("the blank is '{}' and '{}' is a symbol too, they would both be read back as the blank", blank, BLANK));
+        }
+        warnings.push(format!("JFLAP has no way to say which symbol is the blank, so '{}' will be read back as '{}'", blank, BLANK));
+    }
+    if blank != ANY_SYMBOL && machine.alphabet.is_tape(ANY_SYMBOL){
+        return Err(format!("'{}' is a symbol, but it matches any symbol in JFLAP", ANY_SYMBOL));
+    }
+    //the reject state is a plain state in JFLAP, which carries on from it
+    if machine.transitions.keys().any(|(state, _)| *state == machine.reject_state){
+        return Err(format!("the reject state {} has transitions out of it, but JFLAP would take them instead of halting", machine.reject_state));
+    }
+
+    let positions = machine.positions();
+    let ids: BTreeMap<&str, usize> = positions.keys().enumerate().map(|(id, &state)| (state, id)).collect();
+    let symbol = |c: char| if c == blank {String::new()} else {c.to_string()};
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?><structure>\n");
+    let _ = writeln!(xml, "\t<type>{}</type>", TURING_TYPE);
+    if machine.tape_count > 1{
+        let _ = writeln!(xml, "\t<tapes>{}</tapes>", machine.tape_count);
+    }
+    xml.push_str("\t<automaton>\n");
+    for (&state, point) in &positions{
+        let id = ids[state];
+        let _ = writeln!(xml, "\t\t<block id=\"{}\" name=\"{}\">", id, escape(state));
+        let _ = writeln!(xml, "\t\t\t<tag>Machine{}</tag>", id);
+        let _ = writeln!(xml, "\t\t\t<x>{:.1}</x>", point.x);
+        let _ = writeln!(xml, "\t\t\t<y>{:.1}</y>", point.y);
+        if state == machine.start_state{
+            xml.push_str("\t\t\t<initial/>\n");
+        }
+        if state == machine.accept_state{
+            xml.push_str("\t\t\t<final/>\n");
+        }
+        xml.push_str("\t\t</block>\n");
+    }
+
+    let mut transitions: Vec<_> = machine.transitions.iter().collect();
+    transitions.sort_by(|a, b| a.0.cmp(b.0));
+    for ((state, read), choices) in transitions{
+        for transition in choices{
+            xml.push_str("\t\t<transition>\n");
+            let _ = writeln!(xml, "\t\t\t<from>{}</from>", ids[state.as_str()]);
+            let _ = writeln!(xml, "\t\t\t<to>{}</to>", ids[transition.next_state.as_str()]);
+            let tape = |i: usize| (machine.tape_count > 1).then_some(i + 1);
+            for (i, &c) in read.iter().enumerate(){
+                tape_element(&mut xml, "read", tape(i), &symbol(c));
+            }
+            for (i, &c) in transition.write.iter().enumerate(){
+                tape_element(&mut xml, "write", tape(i), &symbol(c));
+            }
+            for (i, movement) in transition.movement.iter().enumerate(){
+                let movement = match movement{
+                    Move::Left => "L",
+                    Move::Right => "R",
+                    Move::Stay => "S",
+                };
+                tape_element(&mut xml, "move", tape(i), movement);
+            }
+            xml.push_str("\t\t</transition>\n");
+        }
+    }
+    xml.push_str("\t</automaton>\n</structure>\n");
+    Ok(Export{xml, warnings})
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// the transitions as sorted `state read next write moves` lines, to compare machines
+    fn transitions(machine: &Machine) -> Vec<String>{
+        let mut lines: Vec<String> = machine.transitions.iter()
+            .flat_map(|((state, read), choices)| choices.iter().map(move |t| format!("{} {} {} {} {:?}", state, read.iter().collect::<String>(), t.next_state, t.write.iter().collect::<String>(), t.movement)))
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn round_trips_through_export(){
+        let mut machine = Machine::parse("q0 0 q0 1 R\nq0 1 q1 0 L\nq0 1 q0 1 S\nq1 _ accept _ S\nq1 0 reject 0 S").unwrap();
+        machine.layout.insert("q0".to_string(), Point{x: 60.0, y: 100.0});
+        let import = import(&export(&machine).unwrap().xml).unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        let imported = import.description.machine;
+        assert_eq!(transitions(&imported), transitions(&machine));
+        assert_eq!(imported.start_state, machine.start_state);
+        assert_eq!(imported.accept_state, machine.accept_state);
+        assert_eq!(imported.alphabet, machine.alphabet);
+        //positions are written to a tenth of a pixel
+        let positions = machine.positions();
+        assert_eq!(imported.layout.len(), positions.len());
+        for (state, point) in &imported.layout{
+            let expected = positions[state.as_str()];
+            assert!((point.x - expected.x).abs() < 0.1 && (point.y - expected.y).abs() < 0.1, "{} moved to {:?}", state, point);
+        }
+        assert_eq!(imported.layout["q0"], Point{x: 60.0, y: 100.0});
+    }
+
+    #[test]
+    fn round_trips_machines_with_several_tapes(){
+        let machine = Machine::parse("q0 0_ q0 00 RR\nq0 __ accept __ SL").unwrap();
+        let xml = export(&machine).unwrap().xml;
+        assert!(xml.contains("<tapes>2</tapes>"));
+        assert!(xml.contains("<read tape=\"2\"/>"));
+        let imported = import(&xml).unwrap().description.machine;
+        assert_eq!(imported.tape_count, 2);
+        assert_eq!(transitions(&imported), transitions(&machine));
+    }
+
+    #[test]
+    fn a_custom_blank_is_read_back_as_the_usual_one(){
+        let mut machine = Machine::parse("q0 0 q0 # R\nq0 # accept # S").unwrap();
+        machine.alphabet = Alphabet::new(['0'], [], '#').unwrap();
+        let export = export(&machine).unwrap();
+        assert_eq!(export.warnings.len(), 1);
+        let imported = import(&export.xml).unwrap().description.machine;
+        assert_eq!(imported.alphabet.blank(), BLANK);
+        assert_eq!(transitions(&imported), ["q0 0 q0 _ [Right]", "q0 _ accept _ [Stay]"]);
+    }
+
+    #[test]
+    fn symbols_jflap_reads_differently_are_refused(){
+        let mut machine = Machine::parse("q0 0 q0 _ R\nq0 # accept # S").unwrap();
+        machine.alphabet = Alphabet::new(['0'], ['_'], '#').unwrap();
+        assert!(export(&machine).is_err());
+        let machine = Machine::parse("q0 ~ accept ~ S").unwrap();
+        assert!(export(&machine).is_err());
+    }
+
+    #[test]
+    fn transitions_out_of_the_reject_state_are_refused(){
+        let machine = Machine::parse("q0 0 reject 0 R\nreject 1 accept 1 S").unwrap();
+        assert!(export(&machine).unwrap_err().contains("reject"));
+    }
+
+    #[test]
+    fn expands_wildcards(){
+        let xml = r#"<structure><type>turing</type><automaton>
+            <block id="0" name="a"><x>0</x><y>0</y><initial/></block>
+            <block id="1" name="b"><x>1</x><y>0</y><final/></block>
+            <transition><from>0</from><to>0</to><read>1</read><write>0</write><move>R</move></transition>
+            <transition><from>0</from><to>1</to><read>~</read><write>~</write><move>S</move></transition>
+        </automaton></structure>"#;
+        let import = import(xml).unwrap();
+        let machine = &import.description.machine;
+        assert_eq!(import.warnings.len(), 1);
+        assert_eq!(import.warnings[0].position, Position{line: 5, column: 13});
+        for symbol in ['0', '1', '_']{
+            assert!(machine.choices("a", &[symbol]).iter().any(|t| t.next_state == "b" && t.write == vec![symbol]), "no transition on {}", symbol);
+        }
+        assert_eq!(machine.choices("a", &['1']).len(), 2);
+    }
+
+    #[test]
+    fn errors_point_at_the_element(){
+        let error = |xml| import(xml).map(|_| ()).unwrap_err().to_string();
+        assert_eq!(error("<structure><type>fa</type></structure>"), "1:1: this is a fa automaton, not a turing machine");
+        assert_eq!(error("<structure><type>turing</type><automaton>\n<block id=\"0\" name=\"a\"/></automaton></structure>"), "1:31: no state is initial");
+        assert_eq!(
+            error("<structure><type>turing</type><automaton><block id=\"0\"><initial/></block>\n<transition><from>0</from><to>0</to><read/><write/><move>X</move></transition></automaton></structure>"),
+            "2:52: \"X\" isn't a move, use L, R or S",
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! headless turing machine simulation.
+//!
+//! everything needed to define, run, save and load a machine without a window.
+//! the game is a front-end over [`Simulation`].
+
+pub mod tape;
+pub mod alphabet;
+pub mod machine;
+pub mod description;
+pub mod import;
+pub mod morphett;
+pub mod jflap;
+pub mod diagram;
+pub mod simulation;
+pub mod explore;
+pub mod save;
+pub mod slots;
+
+pub use tape::{Tape, BLANK};
+pub use alphabet::Alphabet;
+pub use machine::{Machine, Move, Point, Status, Transition};
+pub use description::{Description, ParseError};
+pub use simulation::{Simulation, Track};
+pub use explore::{ComputationTree, Outcome};
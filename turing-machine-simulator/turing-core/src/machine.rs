@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{alphabet::Alphabet, description::Description};
+
+pub const DEFAULT_START_STATE: &str = "q0";
+pub const DEFAULT_ACCEPT_STATE: &str = "accept";
+pub const DEFAULT_REJECT_STATE: &str = "reject";
+/// distance between neighbouring states placed automatically
+const STATE_SPACING: f32 = 120.0;
+/// smallest circle states are placed around, so a few states aren't bunched up
+const MIN_LAYOUT_RADIUS: f32 = 150.0;
+
+/// direction the head moves after a transition
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Move{
+    Left,
+    Right,
+    Stay,
+}
+
+impl Move{
+    pub fn parse(s: &str) -> Option<Move>{
+        match s{
+            "L" | "l" | "<" => Some(Move::Left),
+            "R" | "r" | ">" => Some(Move::Right),
+            "S" | "s" | "N" | "n" | "-" => Some(Move::Stay),
+            _ => None,
+        }
+    }
+}
+
+/// what happens when the machine reads a symbol on each tape in a state.
+/// `write` and `movement` have one entry per tape
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition{
+    pub next_state: String,
+    pub write: Vec<char>,
+    pub movement: Vec<Move>,
+}
+
+/// where a state is drawn in a diagram, with y growing downwards like on screen
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Point{
+    pub x: f32,
+    pub y: f32,
+}
+
+/// whether the machine can keep stepping
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Status{
+    #[default]
+    Running,
+    Accepted,
+    Rejected,
+    /// no transition is defined for the current state and symbol
+    Stuck,
+}
+
+/// a turing machine's definition: its special states, alphabet and transition table.
+///
+/// transitions are keyed by the state and the symbols under every head, one per tape.
+/// a nondeterministic machine has more than one transition, or choice, for some keys.
+#[derive(Clone, Debug)]
+pub struct Machine{
+    pub start_state: String,
+    pub accept_state: String,
+    pub reject_state: String,
+    pub alphabet: Alphabet,
+    /// number of tapes, each with its own head
+    pub tape_count: usize,
+    pub transitions: HashMap<(String, Vec<char>), Vec<Transition>>,
+    /// where states are drawn in a diagram, states without a point are placed automatically
+    pub layout: BTreeMap<String, Point>,
+}
+
+impl Default for Machine{
+    fn default() -> Self {
+        Self{
+            start_state: DEFAULT_START_STATE.to_string(),
+            accept_state: DEFAULT_ACCEPT_STATE.to_string(),
+            reject_state: DEFAULT_REJECT_STATE.to_string(),
+            alphabet: Alphabet::default(),
+            tape_count: 1,
+            transitions: HashMap::new(),
+            layout: BTreeMap::new(),
+        }
+    }
+}
+
+impl Machine{
+    /// parses a machine written in the [description format](crate::description),
+    /// ignoring any initial tapes it gives
+    pub fn parse(program: &str) -> Result<Machine, String>{
+        Description::parse(program).map(|description| description.machine).map_err(|e| e.to_string())
+    }
+
+    /// adds a choice for `state` reading `read`, unless it is already one
+    pub fn add_transition(&mut self, state: String, read: Vec<char>, transition: Transition){
+        let choices = self.transitions.entry((state, read)).or_default();
+        if !choices.contains(&transition){
+            choices.push(transition);
+        }
+    }
+
+    /// the transitions the machine can take in `state` reading `symbols`
+    pub fn choices(&self, state: &str, symbols: &[char]) -> &[Transition]{
+        self.transitions.get(&(state.to_string(), symbols.to_vec())).map_or(&[], Vec::as_slice)
+    }
+
+    /// the transitions from `from` to `to`, with the symbols each reads, in the order of what they read
+    pub fn edge(&self, from: &str, to: &str) -> Vec<(Vec<char>, Transition)>{
+        let mut edge: Vec<(Vec<char>, Transition)> = self.transitions.iter()
+            .filter(|((state, _), _)| state == from)
+            .flat_map(|((_, read), choices)| choices.iter().filter(|t| t.next_state == to).map(|t| (read.clone(), t.clone())))
+            .collect();
+        edge.sort_by(|a, b| a.0.cmp(&b.0));
+        edge
+    }
+
+    /// removes every transition from `from` to `to`
+    pub fn remove_edge(&mut self, from: &str, to: &str){
+        self.transitions.retain(|(state, _), choices| {
+            if state == from{
+                choices.retain(|t| t.next_state != to);
+            }
+            !choices.is_empty()
+        });
+    }
+
+    /// every state the machine can be in: the start and accept states and those the transitions use.
+    /// the reject state is only included if something goes to it
+    pub fn states(&self) -> BTreeSet<&str>{
+        let mut states = BTreeSet::from([self.start_state.as_str(), self.accept_state.as_str()]);
+        for ((state, _), choices) in &self.transitions{
+            states.insert(state);
+            states.extend(choices.iter().map(|t| t.next_state.as_str()));
+        }
+        states
+    }
+
+    /// where every state is drawn. states without a point in the layout are spread around a circle
+    /// to the right of the placed ones, starting with the start state
+    pub fn positions(&self) -> BTreeMap<&str, Point>{
+        let states = self.states();
+        let mut unplaced: Vec<&str> = states.iter().copied().filter(|state| !self.layout.contains_key(*state)).collect();
+        unplaced.sort_by_key(|&state| state != self.start_state);
+        let radius = (STATE_SPACING * unplaced.len() as f32 / std::f32::consts::TAU).max(MIN_LAYOUT_RADIUS);
+        let left = self.layout.values().map(|point| point.x + STATE_SPACING).fold(0.0, f32::max);
+        let center = Point{x: left + radius + STATE_SPACING / 2.0, y: radius + STATE_SPACING / 2.0};
+
+        let mut positions: BTreeMap<&str, Point> = self.layout.iter()
+            .filter(|(state, _)| states.contains(state.as_str()))
+            .map(|(state, &point)| (state.as_str(), point))
+            .collect();
+        for (i, state) in unplaced.iter().enumerate(){
+            //starting from the left, so the start state is on the left like in most diagrams
+            let angle = std::f32::consts::PI + std::f32::consts::TAU * i as f32 / unplaced.len() as f32;
+            positions.insert(state, Point{x: center.x + radius * angle.cos(), y: center.y - radius * angle.sin()});
+        }
+        positions
+    }
+
+    /// whether there is never more than one transition to take
+    pub fn is_deterministic(&self) -> bool{
+        self.transitions.values().all(|choices| choices.len() <= 1)
+    }
+
+    /// the status of a machine sitting in `state` reading `symbols`, one per tape
+    pub fn status(&self, state: &str, symbols: &[char]) -> Status{
+        if state == self.accept_state{
+            Status::Accepted
+        }else if state == self.reject_state{
+            Status::Rejected
+        }else if !self.choices(state, symbols).is_empty(){
+            Status::Running
+        }else{
+            Status::Stuck
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! importing machines written for the online simulator at turingmachinesimulator.com.
+//!
+//! those files give a few `key: value` directives and then every transition as a pair of lines,
+//! the state and the symbol read on each tape, then the next state, the symbols written and the moves:
+//!
+//! ```text
+//! // adds one to a binary number
+//! name: Binary increment
+//! init: right
+//! accept: done
+//!
+//! right,0
+//! right,0,>
+//!
+//! right,_
+//! carry,_,<
+//! ```
+//!
+//! `//` starts a comment, `_` is the blank and moves are `<`, `>` and `-`.
+//! there is no reject state, a machine with nothing to do just halts.
+//!
+//! what this simulator can't represent exactly is converted with a [`Warning`](crate::import::Warning),
+//! anything that can't be converted at all is a [`ParseError`].
+
+use std::collections::HashSet;
+
+use crate::{alphabet::Alphabet, description::{Description, ParseError, Position}, import::{join_accept_states, joining_warnings, unused_state, Import, Warning}, machine::{Machine, Move, Transition, DEFAULT_ACCEPT_STATE, DEFAULT_REJECT_STATE}, tape::{Tape, BLANK}};
+
+const COMMENT_PREFIX: &str = "//";
+const DIRECTIVE_SEPARATOR: char = ':';
+const NAME_DIRECTIVE: &str = "name";
+const INIT_DIRECTIVE: &str = "init";
+const ACCEPT_DIRECTIVE: &str = "accept";
+const FIELD_SEPARATOR: char = ',';
+
+/// a comma separated value along with where it starts
+#[derive(Clone, Copy)]
+struct Field<'a>{
+    text: &'a str,
+    position: Position,
+}
+
+/// splits a line at commas, trimming each field
+fn fields(line_number: usize, line: &str) -> Vec<Field<'_>>{
+    let mut fields = Vec::new();
+    let mut start = (0, 0);
+    let ends = line.char_indices().enumerate()
+        .filter(|(_, (_, c))| *c == FIELD_SEPARATOR)
+        .map(|(column, (byte, _))| (byte, column))
+        .chain([(line.len(), line.chars().count())]);
+    for (end_byte, end_column) in ends{
+        let raw = &line[start.0..end_byte];
+        let leading = raw.chars().take_while(|c| c.is_whitespace()).count();
+        fields.push(Field{text: raw.trim(), position: Position{line: line_number, column: start.1 + leading + 1}});
+        start = (end_byte + FIELD_SEPARATOR.len_utf8(), end_column + 1);
+    }
+    fields
+}
+
+fn symbol(field: Field) -> Result<char, ParseError>{
+    let mut chars = field.text.chars();
+    match (chars.next(), chars.next()){
+        (Some(c), None) => Ok(c),
+        (None, _) => Err(ParseError::new(field.position, "a symbol is missing")),
+        _ => Err(ParseError::new(field.position, format!("\"{}\" isn't a single symbol", field.text))),
+    }
+}
+
+fn state_name(field: Field) -> Result<String, ParseError>{
+    if field.text.is_empty(){
+        return Err(ParseError::new(field.position, "a state is missing"));
+    }
+    Ok(field.text.to_string())
+}
+
+/// converts a machine written for turingmachinesimulator.com
+pub fn import(text: &str) -> Result<Import, ParseError>{
+    let mut name = None;
+    let mut start_state = None;
+    let mut accept_states: Vec<(String, Position)> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut lines = Vec::new();
+
+    for (index, line) in text.lines().enumerate(){
+        let line = line.find(COMMENT_PREFIX).map_or(line, |comment| &line[..comment]);
+        if line.trim().is_empty(){
+            continue;
+        }
+        let position = Position{line: index + 1, column: line.chars().take_while(|c| c.is_whitespace()).count() + 1};
+        //state names can hold a `:` too, so a line with a comma is only a directive if it's one this knows
+        let directive = line.split_once(DIRECTIVE_SEPARATOR)
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .filter(|(key, _)| [NAME_DIRECTIVE, INIT_DIRECTIVE, ACCEPT_DIRECTIVE].contains(key) || !line.contains(FIELD_SEPARATOR));
+        match directive{
+            Some((NAME_DIRECTIVE, value)) => name = Some(value.to_string()),
+            Some((INIT_DIRECTIVE, value)) => start_state = Some((value.to_string(), position)),
+            Some((ACCEPT_DIRECTIVE, _)) => {
+                let value_start = line.find(DIRECTIVE_SEPARATOR).expect("directives have a separator") + DIRECTIVE_SEPARATOR.len_utf8();
+                let offset = line[..value_start].chars().count();
+                for field in fields(index + 1, &line[value_start..]).into_iter().filter(|field| !field.text.is_empty()){
+                    accept_states.push((field.text.to_string(), Position{column: field.position.column + offset, ..field.position}));
+                }
+            },
+            Some((key, _)) => warnings.push(Warning{position, message: format!("{}: isn't supported and was left out", key)}),
+            None => lines.push((index + 1, line)),
+        }
+    }
+
+    let Some((start_state, start_position)) = start_state else{
+        return Err(ParseError::new(Position{line: 1, column: 1}, "init: is missing, so there is no start state"));
+    };
+    if start_state.is_empty(){
+        return Err(ParseError::new(start_position, "init: needs a state"));
+    }
+
+    let mut machine = Machine{
+        start_state,
+        tape_count: 0,
+        ..Machine::default()
+    };
+    let mut used_states = HashSet::from([machine.start_state.clone()]);
+    let mut tape_count_line = 0;
+    for pair in lines.chunks(2){
+        let &[(read_line, read_text), (write_line, write_text)] = pair else{
+            let (line, _) = pair[0];
+            return Err(ParseError::new(Position{line, column: 1}, "this transition has no line saying what to write and where to move"));
+        };
+        let read = fields(read_line, read_text);
+        let write = fields(write_line, write_text);
+        let tape_count = read.len() - 1;
+        if tape_count == 0{
+            return Err(ParseError::new(read[0].position, "a transition needs a state and a symbol for each tape"));
+        }
+        if machine.tape_count == 0{
+            machine.tape_count = tape_count;
+            tape_count_line = read_line;
+        }else if tape_count != machine.tape_count{
+            return Err(ParseError::new(read[0].position, format!("reads {} tapes but line {} reads {}", tape_count, tape_count_line, machine.tape_count)));
+        }
+        if write.len() != 1 + 2 * tape_count{
+            let position = write.get(1 + 2 * tape_count).map_or(write[write.len() - 1].position, |extra| extra.position);
+            return Err(ParseError::new(position, format!("needs the next state, then a symbol to write and a move for each of the {} tapes", tape_count)));
+        }
+
+        let state = state_name(read[0])?;
+        let symbols = read[1..].iter().copied().map(symbol).collect::<Result<Vec<_>, _>>()?;
+        let next_state = state_name(write[0])?;
+        let written = write[1..=tape_count].iter().copied().map(symbol).collect::<Result<Vec<_>, _>>()?;
+        let movement = write[tape_count + 1..].iter()
+            .map(|field| match field.text{
+                "<" | ">" | "-" => Ok(Move::parse(field.text).expect("the online simulator's moves are understood")),
+                _ => Err(ParseError::new(field.position, format!("\"{}\" isn't a move, use <, > or -", field.text))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !machine.choices(&state, &symbols).is_empty(){
+            warnings.push(Warning{position: read[0].position, message: format!("{} already has a transition for this, so the machine is nondeterministic", state)});
+        }
+        used_states.insert(state.clone());
+        used_states.insert(next_state.clone());
+        machine.add_transition(state, symbols, Transition{next_state, write: written, movement});
+    }
+    machine.tape_count = machine.tape_count.max(1);
+    used_states.extend(accept_states.iter().map(|(state, _)| state.clone()));
+
+    machine.reject_state = unused_state(DEFAULT_REJECT_STATE, &used_states);
+    let tapes = vec![Tape::new(BLANK); machine.tape_count];
+    machine.alphabet = Alphabet::covering(&tapes, &machine);
+    match accept_states.split_first(){
+        None => machine.accept_state = unused_state(DEFAULT_ACCEPT_STATE, &used_states),
+        Some(((accept_state, _), extra)) => {
+            machine.accept_state = accept_state.clone();
+            if let Some((_, position)) = extra.first(){
+                let extra: Vec<String> = extra.iter().map(|(state, _)| state.clone()).collect();
+                let dropped = join_accept_states(&mut machine, &extra).map_err(|e| ParseError::new(*position, e))?;
+                warnings.extend(joining_warnings(accept_state, &extra, dropped, *position));
+            }
+        },
+    }
+
+    Ok(Import{
+        name,
+        description: Description{
+            machine,
+            declares_alphabet: false,
+            tapes,
+        },
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Status;
+
+    const INCREMENT: &str = "// adds one to a binary number
+name: Binary increment
+init: right
+accept: done
+
+right,0
+right,0,>
+
+right,1
+right,1,>
+
+right,_
+carry,_,<
+
+carry,1
+carry,0,<
+
+carry,0
+done,1,-
+
+carry,_
+done,1,-
+";
+
+    #[test]
+    fn imports_the_example(){
+        let import = import(INCREMENT).unwrap();
+        assert_eq!(import.name.as_deref(), Some("Binary increment"));
+        assert!(import.warnings.is_empty());
+        let machine = &import.description.machine;
+        assert_eq!((machine.start_state.as_str(), machine.accept_state.as_str()), ("right", "done"));
+        assert_eq!(machine.tape_count, 1);
+        assert!(!machine.states().contains(machine.reject_state.as_str()));
+
+        let mut simulation = crate::Simulation::new(vec![Tape::from_cells("1011")], machine.clone());
+        assert_eq!(simulation.run(100), Status::Accepted);
+        assert_eq!(simulation.tracks[0].tape.iter().map(|(_, c)| c).collect::<String>(), "1100");
+    }
+
+    #[test]
+    fn several_accept_states_are_joined(){
+        let import = import("init: q0\naccept: a, b\nq0,0\nb,0,-").unwrap();
+        let machine = &import.description.machine;
+        assert_eq!(machine.accept_state, "a");
+        assert_eq!(machine.choices("b", &['0'])[0].next_state, "a");
+        assert_eq!(import.warnings.len(), 1);
+        assert_eq!(import.warnings[0].position, Position{line: 2, column: 12});
+    }
+
+    #[test]
+    fn reads_several_tapes(){
+        let import = import("init: q0\nq0,0,_\nq0,0,0,>,-").unwrap();
+        let machine = &import.description.machine;
+        assert_eq!(machine.tape_count, 2);
+        assert_eq!(import.description.tapes.len(), 2);
+        assert_eq!(machine.choices("q0", &['0', '_'])[0].movement, vec![Move::Right, Move::Stay]);
+    }
+
+    #[test]
+    fn state_names_can_hold_colons(){
+        let import = import("init: q:1\naccept: q:2\nq:1,0\nq:2,1,>").unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        let machine = &import.description.machine;
+        assert_eq!(machine.start_state, "q:1");
+        assert_eq!(machine.choices("q:1", &['0'])[0].next_state, "q:2");
+    }
+
+    #[test]
+    fn warns_about_what_changes_meaning(){
+        let import = import("init: q0\ngraph: yes\nq0,0\nq0,1,>\nq0,0\nq1,1,<").unwrap();
+        let messages: Vec<String> = import.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "2:1: graph: isn't supported and was left out".to_string(),
+            "5:1: q0 already has a transition for this, so the machine is nondeterministic".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn errors_point_at_the_field(){
+        let error = |text| import(text).map(|_| ()).unwrap_err().to_string();
+        assert_eq!(error("q0,0\nq0,1,>"), "1:1: init: is missing, so there is no start state");
+        assert_eq!(error("init: q0\nq0,0\nq0,1,x"), "3:6: \"x\" isn't a move, use <, > or -");
+        assert_eq!(error("init: q0\nq0, 01\nq0,1,>"), "2:5: \"01\" isn't a single symbol");
+        assert_eq!(error("init: q0\nq0,0"), "2:1: this transition has no line saying what to write and where to move");
+        assert_eq!(error("init: q0\nq0,0\nq0,1,>,>"), "3:8: needs the next state, then a symbol to write and a move for each of the 1 tapes");
+    }
+}
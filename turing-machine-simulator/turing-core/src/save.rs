@@ -0,0 +1,666 @@
+//! reading and writing worlds to disk.
+//!
+//! a world is a `.sav` file written as RON. it starts with a format version and
+//! timestamps, followed by the machine state, step count, tapes and program.
+//! each tape keeps its head position, and transitions read, write and move with
+//! one character or move per tape. a nondeterministic machine lists every choice for a state
+//! and its read symbols, in order:
+//!
+//! ```text
+//! (
+//!     version: 3,
+//!     created: 1760000000,
+//!     modified: 1760000000,
+//!     state: "q0",
+//!     steps: 0,
+//!     tapes: [
+//!         (head: 0, offset: -1, cells: "1_01"),
+//!     ],
+//!     program: (
+//!         start_state: "q0",
+//!         accept_state: "accept",
+//!         reject_state: "reject",
+//!         alphabet: Some((input: "01", tape: "01_", blank: '_')),
+//!         transitions: [
+//!             (state: "q0", read: "1", next_state: "q0", write: "0", movement: [Right]),
+//!         ],
+//!         layout: {
+//!             "q0": (x: 0.0, y: 0.0),
+//!         },
+//!     ),
+//! )
+//! ```
+//!
+//! `layout` holds where states are drawn in a diagram and is left out until one is placed.
+//!
+//! versions 1 and 2 have a single tape with the head stored beside it, and single characters
+//! in their transitions. version 1 saves have no alphabet either. they get the default one,
+//! extended with every symbol on their tape and in their program.
+//!
+//! files from before the format was versioned hold only the tape, optionally preceded by an
+//! `@<offset>` line, and are migrated when read. anything that isn't a RON struct with a
+//! `version` field is taken to be one of them. if a `.tm` [description](crate::description)
+//! sits next to a save it replaces the saved program, so machines can be written in an external
+//! editor. the saved alphabet is kept unless the description gives one, and a description without
+//! a save starts a new world on its initial tapes.
+
+use std::{collections::BTreeMap, error::Error, fmt, fs, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+use serde::{Deserialize, Serialize};
+
+use crate::{alphabet::Alphabet, description::Description, machine::{Machine, Move, Point, Transition}, simulation::Track, tape::{Tape, BLANK}, Simulation};
+
+/// version written to new saves
+pub const SAVE_VERSION: u32 = 3;
+/// first version whose programs have an alphabet
+const ALPHABET_VERSION: u32 = 2;
+/// first version with a list of tapes, each with its own head
+const TAPES_VERSION: u32 = 3;
+const LEGACY_OFFSET_PREFIX: char = '@';
+/// the field every versioned save starts with, which legacy tapes are told apart by
+const VERSION_FIELD: &str = "version";
+
+/// why a save couldn't be read or written
+#[derive(Debug)]
+pub enum SaveError{
+    /// there is no save at the path
+    NotFound(PathBuf),
+    /// the file exists but couldn't be read or written
+    Io{path: PathBuf, source: io::Error},
+    /// the save isn't valid RON or is missing fields
+    Corrupted(String),
+    /// the save was written by a newer version of the game
+    UnsupportedVersion(u32),
+    /// the program next to the save couldn't be parsed
+    Program(String),
+    /// a save can't be given this name
+    InvalidName(String),
+    /// a save with this name already exists
+    AlreadyExists(String),
+}
+
+impl SaveError{
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self{
+        match source.kind(){
+            io::ErrorKind::NotFound => SaveError::NotFound(path.to_path_buf()),
+            _ => SaveError::Io{path: path.to_path_buf(), source},
+        }
+    }
+}
+
+impl fmt::Display for SaveError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            SaveError::NotFound(path) => write!(f, "no save found at {}", path.display()),
+            SaveError::Io{path, source} => write!(f, "could not access {}: {}", path.display(), source),
+            SaveError::Corrupted(e) => write!(f, "the save is corrupted: {}", e),
+            SaveError::UnsupportedVersion(v) => write!(f, "the save is format version {} but only versions up to {} are supported", v, SAVE_VERSION),
+            SaveError::Program(e) => write!(f, "the program could not be read: {}", e),
+            SaveError::InvalidName(name) => write!(f, "\"{}\" isn't a valid name, use letters, numbers, spaces, - and _", name),
+            SaveError::AlreadyExists(name) => write!(f, "a save called \"{}\" already exists", name),
+        }
+    }
+}
+
+impl Error for SaveError{
+    fn source(&self) -> Option<&(dyn Error + 'static)>{
+        match self{
+            SaveError::Io{source, ..} => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// information about a save that isn't part of the simulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata{
+    /// format version the save was read from
+    pub version: u32,
+    /// seconds since the unix epoch
+    pub created: u64,
+    /// seconds since the unix epoch
+    pub modified: u64,
+}
+
+impl Default for Metadata{
+    fn default() -> Self {
+        let now = now();
+        Self{
+            version: SAVE_VERSION,
+            created: now,
+            modified: now,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile{
+    version: u32,
+    created: u64,
+    modified: u64,
+    state: String,
+    steps: u64,
+    tapes: Vec<SavedTape>,
+    program: SavedProgram,
+}
+
+/// a save from before machines had several tapes
+#[derive(Deserialize)]
+struct SingleTapeSaveFile{
+    version: u32,
+    created: u64,
+    modified: u64,
+    head: i64,
+    state: String,
+    steps: u64,
+    tape: SavedTape,
+    program: SingleTapeProgram,
+}
+
+/// just enough of a save to find out which version it is
+#[derive(Deserialize)]
+struct SaveHeader{
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedTape{
+    /// kept with the save rather than the tape before [`TAPES_VERSION`]
+    #[serde(default)]
+    head: i64,
+    /// index of the first cell in `cells`
+    offset: i64,
+    cells: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedProgram{
+    start_state: String,
+    accept_state: String,
+    reject_state: String,
+    /// missing from saves older than [`ALPHABET_VERSION`]
+    #[serde(default)]
+    alphabet: Option<SavedAlphabet>,
+    transitions: Vec<SavedTransition>,
+    /// left out when no state has been placed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    layout: BTreeMap<String, Point>,
+}
+
+#[derive(Deserialize)]
+struct SingleTapeProgram{
+    start_state: String,
+    accept_state: String,
+    reject_state: String,
+    #[serde(default)]
+    alphabet: Option<SavedAlphabet>,
+    transitions: Vec<SingleTapeTransition>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedAlphabet{
+    input: String,
+    /// includes the input symbols and the blank
+    tape: String,
+    blank: char,
+}
+
+/// `read` and `write` have one character per tape and `movement` one move per tape
+#[derive(Serialize, Deserialize)]
+struct SavedTransition{
+    state: String,
+    read: String,
+    next_state: String,
+    write: String,
+    movement: Vec<Move>,
+}
+
+#[derive(Deserialize)]
+struct SingleTapeTransition{
+    state: String,
+    read: char,
+    next_state: String,
+    write: char,
+    movement: Move,
+}
+
+impl From<SingleTapeSaveFile> for SaveFile{
+    fn from(save: SingleTapeSaveFile) -> Self {
+        let program = save.program;
+        Self{
+            version: save.version,
+            created: save.created,
+            modified: save.modified,
+            state: save.state,
+            steps: save.steps,
+            tapes: vec![SavedTape{head: save.head, ..save.tape}],
+            program: SavedProgram{
+                start_state: program.start_state,
+                accept_state: program.accept_state,
+                reject_state: program.reject_state,
+                alphabet: program.alphabet,
+                layout: BTreeMap::new(),
+                transitions: program.transitions.into_iter()
+                    .map(|t| SavedTransition{
+                        state: t.state,
+                        read: t.read.to_string(),
+                        next_state: t.next_state,
+                        write: t.write.to_string(),
+                        movement: vec![t.movement],
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl From<&Track> for SavedTape{
+    fn from(track: &Track) -> Self {
+        let tape = &track.tape;
+        match tape.bounds(){
+            None => Self{head: track.head, offset: 0, cells: String::new()},
+            Some((first, last)) => Self{
+                head: track.head,
+                offset: first,
+                cells: (first..=last).map(|i| tape[i]).collect(),
+            },
+        }
+    }
+}
+
+impl SavedTape{
+    /// the saved cells on a tape whose empty cells hold `blank`
+    fn to_tape(&self, blank: char) -> Tape{
+        let mut tape = Tape::new(blank);
+        for (i, c) in self.cells.chars().enumerate(){
+            tape.set(self.offset + i as i64, c);
+        }
+        tape
+    }
+}
+
+impl From<&Alphabet> for SavedAlphabet{
+    fn from(alphabet: &Alphabet) -> Self {
+        Self{
+            input: alphabet.input().collect(),
+            tape: alphabet.tape().collect(),
+            blank: alphabet.blank(),
+        }
+    }
+}
+
+impl TryFrom<&SavedAlphabet> for Alphabet{
+    type Error = String;
+
+    fn try_from(saved: &SavedAlphabet) -> Result<Self, String> {
+        Alphabet::new(saved.input.chars(), saved.tape.chars(), saved.blank)
+    }
+}
+
+impl From<&Machine> for SavedProgram{
+    fn from(machine: &Machine) -> Self {
+        let mut transitions: Vec<SavedTransition> = machine.transitions.iter()
+            .flat_map(|((state, read), choices)| choices.iter().map(move |t| SavedTransition{
+                state: state.clone(),
+                read: read.iter().collect(),
+                next_state: t.next_state.clone(),
+                write: t.write.iter().collect(),
+                movement: t.movement.clone(),
+            }))
+            .collect();
+        //keeps saves diffable, the sort is stable so choices stay in order
+        transitions.sort_by(|a, b| (&a.state, &a.read).cmp(&(&b.state, &b.read)));
+        Self{
+            start_state: machine.start_state.clone(),
+            accept_state: machine.accept_state.clone(),
+            reject_state: machine.reject_state.clone(),
+            alphabet: Some(SavedAlphabet::from(&machine.alphabet)),
+            transitions,
+            layout: machine.layout.clone(),
+        }
+    }
+}
+
+impl SavedProgram{
+    /// the machine for a save with `tape_count` tapes.
+    /// the alphabet is left as the default, as it may need the tapes to work out
+    fn into_machine(self, tape_count: usize) -> Result<Machine, String>{
+        let mut machine = Machine{
+            start_state: self.start_state,
+            accept_state: self.accept_state,
+            reject_state: self.reject_state,
+            tape_count,
+            layout: self.layout,
+            ..Default::default()
+        };
+        for t in self.transitions{
+            let read: Vec<char> = t.read.chars().collect();
+            let write: Vec<char> = t.write.chars().collect();
+            if read.len() != tape_count || write.len() != tape_count || t.movement.len() != tape_count{
+                return Err(format!("the transition from {} on \"{}\" doesn't have one symbol and move for each of the {} tapes", t.state, t.read, tape_count));
+            }
+            machine.add_transition(t.state, read, Transition{next_state: t.next_state, write, movement: t.movement});
+        }
+        Ok(machine)
+    }
+}
+
+/// seconds since the unix epoch
+pub fn now() -> u64{
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// path of the program belonging to a `.sav` file
+pub fn program_path(save_path: &Path) -> PathBuf{
+    save_path.with_extension("tm")
+}
+
+/// parses the contents of a save, migrating it if it is from an older version
+pub fn decode_world(contents: &str) -> Result<(Simulation, Metadata), SaveError>{
+    //old saves are just the tape, which can hold anything the player wrote, even something starting like RON
+    let trimmed = contents.trim_start();
+    if !trimmed.starts_with('(') || !trimmed.contains(VERSION_FIELD){
+        return Ok(migrate_legacy(contents));
+    }
+
+    let header: SaveHeader = ron::from_str(contents).map_err(|e| SaveError::Corrupted(e.to_string()))?;
+    let mut save: SaveFile = match header.version{
+        0 => return Err(SaveError::Corrupted("version 0 was never written".to_string())),
+        1..TAPES_VERSION => ron::from_str::<SingleTapeSaveFile>(contents).map_err(|e| SaveError::Corrupted(e.to_string()))?.into(),
+        TAPES_VERSION..=SAVE_VERSION => ron::from_str(contents).map_err(|e| SaveError::Corrupted(e.to_string()))?,
+        v => return Err(SaveError::UnsupportedVersion(v)),
+    };
+    if save.tapes.is_empty(){
+        return Err(SaveError::Corrupted("the world has no tapes".to_string()));
+    }
+
+    let saved_alphabet = save.program.alphabet.take();
+    if saved_alphabet.is_none() && save.version >= ALPHABET_VERSION{
+        return Err(SaveError::Corrupted("the program has no alphabet".to_string()));
+    }
+    let mut machine = save.program.into_machine(save.tapes.len()).map_err(SaveError::Corrupted)?;
+    let tapes = match saved_alphabet{
+        Some(saved) => {
+            machine.alphabet = Alphabet::try_from(&saved).map_err(SaveError::Corrupted)?;
+            machine.alphabet.check(&machine).map_err(SaveError::Corrupted)?;
+            let tapes: Vec<Tape> = save.tapes.iter().map(|tape| tape.to_tape(saved.blank)).collect();
+            for tape in &tapes{
+                if let Some((i, c)) = tape.iter().find(|&(_, c)| !machine.alphabet.is_tape(c)){
+                    return Err(SaveError::Corrupted(format!("cell {} holds '{}', which isn't in the tape alphabet", i, c)));
+                }
+            }
+            tapes
+        },
+        None => {
+            let tapes: Vec<Tape> = save.tapes.iter().map(|tape| tape.to_tape(BLANK)).collect();
+            machine.alphabet = Alphabet::covering(&tapes, &machine);
+            tapes
+        },
+    };
+
+    let mut simulation = Simulation::new(tapes, machine);
+    for (track, saved) in simulation.tracks.iter_mut().zip(&save.tapes){
+        track.head = saved.head;
+    }
+    simulation.state = save.state;
+    simulation.steps = save.steps;
+    simulation.update_status();
+    Ok((simulation, Metadata{version: save.version, created: save.created, modified: save.modified}))
+}
+
+/// writes a world as the current save version
+pub fn encode_world(simulation: &Simulation, metadata: &Metadata) -> String{
+    let save = SaveFile{
+        version: SAVE_VERSION,
+        created: metadata.created,
+        modified: metadata.modified,
+        state: simulation.state.clone(),
+        steps: simulation.steps,
+        tapes: simulation.tracks.iter().map(SavedTape::from).collect(),
+        program: SavedProgram::from(&simulation.machine),
+    };
+    ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default().depth_limit(3)).expect("saves are always serializable")
+}
+
+/// reads the world at `path`, using the program next to it if there is one.
+/// if there is a program but no save yet, the world starts from the program's initial tapes
+pub fn read_world(path: &Path) -> Result<(Simulation, Metadata), SaveError>{
+    let program_path = program_path(path);
+    let description = match read_description(&program_path){
+        Ok(description) => Some(description),
+        Err(SaveError::NotFound(_)) => None,
+        Err(e) => return Err(e),
+    };
+    let (contents, description) = match (fs::read_to_string(path), description){
+        (Ok(contents), description) => (contents, description),
+        (Err(e), Some(description)) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok((description.into_simulation(), Metadata::default()));
+        },
+        (Err(e), _) => return Err(SaveError::io(path, e)),
+    };
+
+    let (mut simulation, metadata) = decode_world(&contents)?;
+    if let Some(description) = description{
+        apply_program(&mut simulation, description)?;
+    }
+    Ok((simulation, metadata))
+}
+
+/// swaps the machine of a world for the one in `description`, keeping its tapes and where its states are drawn.
+/// programs that don't give an alphabet keep the world's
+pub fn apply_program(simulation: &mut Simulation, mut description: Description) -> Result<(), SaveError>{
+    description.machine.layout = simulation.machine.layout.clone();
+    if !description.declares_alphabet{
+        description.machine.alphabet = simulation.machine.alphabet.clone();
+        description.machine.alphabet.check(&description.machine).map_err(SaveError::Program)?;
+    }
+    simulation.replace_machine(description.machine);
+    Ok(())
+}
+
+/// reads the machine description at `path`
+pub fn read_description(path: &Path) -> Result<Description, SaveError>{
+    let text = fs::read_to_string(path).map_err(|e| SaveError::io(path, e))?;
+    Description::parse(&text).map_err(|e| SaveError::Program(e.to_string()))
+}
+
+/// writes the world to `path`, updating its modified time.
+/// the save is written to a temporary file first so a failed write never destroys the old save,
+/// and a save read from an older version is [backed up](back_up) first in case it was misread.
+pub fn write_world(path: &Path, simulation: &Simulation, metadata: &mut Metadata) -> Result<(), SaveError>{
+    if metadata.version < SAVE_VERSION{
+        match back_up(path){
+            Ok(_) | Err(SaveError::NotFound(_)) => (),
+            Err(e) => return Err(e),
+        }
+    }
+    metadata.modified = now();
+    metadata.version = SAVE_VERSION;
+    let temp_path = path.with_extension("sav.tmp");
+    fs::write(&temp_path, encode_world(simulation, metadata)).map_err(|e| SaveError::io(&temp_path, e))?;
+    fs::rename(&temp_path, path).map_err(|e| SaveError::io(path, e))
+}
+
+/// moves a save aside so writing a new one doesn't overwrite it, returning where it went
+pub fn back_up(path: &Path) -> Result<PathBuf, SaveError>{
+    let backup_path = path.with_extension(format!("sav.{}.bak", now()));
+    fs::rename(path, &backup_path).map_err(|e| SaveError::io(path, e))?;
+    Ok(backup_path)
+}
+
+/// a save from before versioning: the tape from the origin, or from an `@<offset>` line
+fn migrate_legacy(contents: &str) -> (Simulation, Metadata){
+    let (start, cells) = contents.strip_prefix(LEGACY_OFFSET_PREFIX)
+        .and_then(|rest| rest.split_once('\n'))
+        .and_then(|(offset, cells)| Some((offset.parse::<i64>().ok()?, cells)))
+        .unwrap_or((0, contents));
+
+    let mut tape = Tape::default();
+    for (i, c) in cells.chars().enumerate(){
+        if c != BLANK{
+            tape.set(start + i as i64, c);
+        }
+    }
+    let tapes = vec![tape];
+    let mut machine = Machine::default();
+    machine.alphabet = Alphabet::covering(&tapes, &machine);
+    (Simulation::new(tapes, machine), Metadata{version: 0, ..Default::default()})
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::Status;
+
+    fn cells(tape: &Tape) -> Vec<(i64, char)>{
+        tape.iter().collect()
+    }
+
+    #[test]
+    fn migrates_unversioned_saves(){
+        let (simulation, metadata) = decode_world("1_01").unwrap();
+        assert_eq!(metadata.version, 0);
+        assert_eq!(cells(&simulation.tracks[0].tape), vec![(0, '1'), (2, '0'), (3, '1')]);
+
+        let (simulation, _) = decode_world("@-3\nab").unwrap();
+        assert_eq!(cells(&simulation.tracks[0].tape), vec![(-3, 'a'), (-2, 'b')]);
+        assert!(simulation.machine.alphabet.is_tape('a'));
+        assert_eq!(simulation.state, "q0");
+    }
+
+    #[test]
+    fn unversioned_saves_can_start_like_ron(){
+        let (simulation, metadata) = decode_world("(1_0").unwrap();
+        assert_eq!(metadata.version, 0);
+        assert_eq!(cells(&simulation.tracks[0].tape), vec![(0, '('), (1, '1'), (3, '0')]);
+    }
+
+    #[test]
+    fn migrates_version_1(){
+        let save = r#"(
+            version: 1,
+            created: 10,
+            modified: 20,
+            head: 2,
+            state: "q1",
+            steps: 5,
+            tape: (offset: -1, cells: "x_1"),
+            program: (
+                start_state: "q0",
+                accept_state: "accept",
+                reject_state: "reject",
+                transitions: [
+                    (state: "q1", read: '1', next_state: "q1", write: 'y', movement: Right),
+                ],
+            ),
+        )"#;
+        let (simulation, metadata) = decode_world(save).unwrap();
+        assert_eq!(metadata, Metadata{version: 1, created: 10, modified: 20});
+        assert_eq!(simulation.tracks.len(), 1);
+        assert_eq!(simulation.tracks[0].head, 2);
+        assert_eq!(cells(&simulation.tracks[0].tape), vec![(-1, 'x'), (1, '1')]);
+        assert_eq!((simulation.state.as_str(), simulation.steps), ("q1", 5));
+        //the alphabet covers what the tape and program use
+        for symbol in ['0', '1', 'x', 'y', '_']{
+            assert!(simulation.machine.alphabet.is_tape(symbol), "{} is missing", symbol);
+        }
+        assert_eq!(simulation.machine.choices("q1", &['1'])[0].movement, vec![Move::Right]);
+    }
+
+    #[test]
+    fn migrates_version_2(){
+        let save = r#"(
+            version: 2,
+            created: 10,
+            modified: 20,
+            head: 0,
+            state: "q0",
+            steps: 0,
+            tape: (offset: 0, cells: "ab"),
+            program: (
+                start_state: "q0",
+                accept_state: "accept",
+                reject_state: "reject",
+                alphabet: Some((input: "ab", tape: "ab#", blank: '#')),
+                transitions: [
+                    (state: "q0", read: 'a', next_state: "accept", write: 'b', movement: Stay),
+                ],
+            ),
+        )"#;
+        let (mut simulation, metadata) = decode_world(save).unwrap();
+        assert_eq!(metadata.version, 2);
+        assert_eq!(simulation.machine.alphabet.blank(), '#');
+        assert_eq!(simulation.tracks[0].tape.blank(), '#');
+        assert_eq!(simulation.step(), Status::Accepted);
+        assert_eq!(simulation.tracks[0].read(), 'b');
+    }
+
+    #[test]
+    fn migrated_saves_are_written_as_the_current_version(){
+        let (simulation, mut metadata) = decode_world("@-1\n1_0").unwrap();
+        metadata.version = SAVE_VERSION;
+        let encoded = encode_world(&simulation, &metadata);
+        let (decoded, decoded_metadata) = decode_world(&encoded).unwrap();
+        assert_eq!(decoded_metadata.version, SAVE_VERSION);
+        assert_eq!(cells(&decoded.tracks[0].tape), cells(&simulation.tracks[0].tape));
+    }
+
+    #[test]
+    fn round_trips_machines_with_several_tapes(){
+        let mut simulation = Description::parse("tapes: 2\ninitial: 01\nq0 0_ q0 00 RR\nq0 0_ q1 0_ SL\nq1 1_ accept 11 SS").unwrap().into_simulation();
+        simulation.machine.layout.insert("q0".to_string(), Point{x: 1.0, y: 2.0});
+        simulation.step();
+        let encoded = encode_world(&simulation, &Metadata::default());
+        let (decoded, _) = decode_world(&encoded).unwrap();
+        assert_eq!(decoded.tracks.iter().map(|track| track.head).collect::<Vec<_>>(), vec![1, 1]);
+        assert_eq!(cells(&decoded.tracks[1].tape), vec![(0, '0')]);
+        assert_eq!(decoded.machine.choices("q0", &['0', '_']), simulation.machine.choices("q0", &['0', '_']));
+        assert_eq!(decoded.machine.layout, simulation.machine.layout);
+        assert_eq!(decoded.steps, 1);
+    }
+
+    #[test]
+    fn rejects_newer_and_broken_saves(){
+        assert!(matches!(decode_world("(version: 99)"), Err(SaveError::UnsupportedVersion(99))));
+        assert!(matches!(decode_world("(version: 3, created: 0)"), Err(SaveError::Corrupted(_))));
+        assert!(matches!(decode_world("(version: 0)"), Err(SaveError::Corrupted(_))));
+    }
+
+    #[test]
+    fn older_saves_are_backed_up_before_being_written_over(){
+        let dir = std::env::temp_dir().join(format!("turing-core-save-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("old.sav");
+        fs::write(&path, "1_01").unwrap();
+        let (simulation, mut metadata) = read_world(&path).unwrap();
+        write_world(&path, &simulation, &mut metadata).unwrap();
+        let backups: Vec<PathBuf> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).filter(|path| path != &dir.join("old.sav")).collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "1_01");
+        assert_eq!(read_world(&path).unwrap().1.version, SAVE_VERSION);
+
+        //a damaged save on disk is refused rather than read as a tape and saved over
+        let encoded = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &encoded[..encoded.len() / 2]).unwrap();
+        assert!(matches!(read_world(&path), Err(SaveError::Corrupted(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn damaged_saves_are_corrupted_not_legacy(){
+        let encoded = encode_world(&Description::parse("q0 0 accept 1 R").unwrap().into_simulation(), &Metadata::default());
+        let truncated = &encoded[..encoded.len() / 2];
+        assert!(matches!(decode_world(truncated), Err(SaveError::Corrupted(_))));
+        let typo = encoded.replacen(",", ",,", 1);
+        assert!(matches!(decode_world(&typo), Err(SaveError::Corrupted(_))));
+    }
+
+    #[test]
+    fn programs_keep_where_states_are_drawn(){
+        let mut simulation = Description::parse("q0 0 accept 1 R").unwrap().into_simulation();
+        simulation.machine.layout.insert("q0".to_string(), crate::Point{x: 10.0, y: 20.0});
+        apply_program(&mut simulation, Description::parse("q0 0 q1 1 R\nq1 _ accept _ S").unwrap()).unwrap();
+        assert_eq!(simulation.machine.layout["q0"], crate::Point{x: 10.0, y: 20.0});
+        assert!(simulation.machine.transitions.contains_key(&("q1".to_string(), vec!['_'])));
+    }
+}
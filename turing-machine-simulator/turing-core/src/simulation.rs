@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+
+use crate::{machine::{Machine, Move, Status, Transition}, tape::Tape};
+
+/// number of steps that can be undone
+pub const HISTORY_LIMIT: usize = 100_000;
+
+/// what a step overwrote, so it can be undone
+#[derive(Clone, Debug)]
+struct StepRecord{
+    heads: Vec<i64>,
+    state: String,
+    symbols: Vec<char>,
+}
+
+/// one of the machine's tapes along with its head
+#[derive(Clone, Debug, Default)]
+pub struct Track{
+    pub tape: Tape,
+    /// index of the cell under the head, negative to the left of the origin
+    pub head: i64,
+}
+
+impl Track{
+    pub fn new(tape: Tape) -> Self{
+        Self{
+            tape,
+            head: 0,
+        }
+    }
+
+    /// symbol under the head
+    pub fn read(&self) -> char{
+        self.tape[self.head]
+    }
+
+    /// writes a symbol under the head
+    pub fn write(&mut self, symbol: char){
+        self.tape.set(self.head, symbol);
+    }
+
+    /// blanks the cell under the head
+    pub fn erase(&mut self){
+        self.write(self.tape.blank());
+    }
+
+    /// moves the head, returning whether it moved
+    pub fn move_head(&mut self, movement: Move) -> bool{
+        match movement{
+            Move::Left => self.head -= 1,
+            Move::Right => self.head += 1,
+            Move::Stay => return false,
+        }
+        true
+    }
+}
+
+/// writes and moves every head as `transition` says
+pub(crate) fn apply(tracks: &mut [Track], transition: &Transition){
+    for ((track, &symbol), &movement) in tracks.iter_mut().zip(&transition.write).zip(&transition.movement){
+        track.write(symbol);
+        track.move_head(movement);
+    }
+}
+
+/// a machine running over its tapes
+#[derive(Clone, Debug)]
+pub struct Simulation{
+    /// one per tape of the machine, the input goes on the first
+    pub tracks: Vec<Track>,
+    pub machine: Machine,
+    /// name of the state the machine is in
+    pub state: String,
+    pub status: Status,
+    /// number of transitions taken since the last reset
+    pub steps: u64,
+    history: VecDeque<StepRecord>,
+}
+
+impl Default for Simulation{
+    fn default() -> Self {
+        Self::new(Vec::new(), Machine::default())
+    }
+}
+
+impl Simulation{
+    /// runs `machine` over `tapes`, whose empty cells take the machine's blank.
+    /// tapes are added or dropped to match the machine's tape count
+    pub fn new(tapes: Vec<Tape>, machine: Machine) -> Self{
+        let mut simulation = Self{
+            tracks: tapes.into_iter().map(Track::new).collect(),
+            state: machine.start_state.clone(),
+            machine,
+            status: Status::Running,
+            steps: 0,
+            history: VecDeque::new(),
+        };
+        simulation.fit_tracks();
+        simulation
+    }
+
+    /// swaps in a new definition, keeping the tapes, heads and state
+    pub fn replace_machine(&mut self, mut machine: Machine){
+        //machines written as text have no layout, so states that are still there stay where they were
+        if machine.layout.is_empty(){
+            let states = machine.states();
+            machine.layout = self.machine.layout.iter()
+                .filter(|(state, _)| states.contains(state.as_str()))
+                .map(|(state, &point)| (state.clone(), point))
+                .collect();
+        }
+        self.machine = machine;
+        self.fit_tracks();
+        self.history.clear();
+        self.update_status();
+    }
+
+    /// makes there be one track per tape, each using the machine's blank
+    fn fit_tracks(&mut self){
+        self.tracks.resize_with(self.machine.tape_count, Track::default);
+        for track in &mut self.tracks{
+            track.tape.set_blank(self.machine.alphabet.blank());
+        }
+    }
+
+    /// works out the status from the current state and symbols
+    pub fn update_status(&mut self){
+        self.status = self.machine.status(&self.state, &self.read());
+    }
+
+    /// puts the machine back into its start state without touching the tapes
+    pub fn reset_machine(&mut self){
+        self.state = self.machine.start_state.clone();
+        self.status = Status::Running;
+        self.steps = 0;
+        self.history.clear();
+    }
+
+    /// symbols under every head
+    pub fn read(&self) -> Vec<char>{
+        self.tracks.iter().map(Track::read).collect()
+    }
+
+    /// the transitions that can be taken from here, more than one if the machine is nondeterministic
+    pub fn choices(&self) -> &[Transition]{
+        self.machine.choices(&self.state, &self.read())
+    }
+
+    /// performs a single transition, taking the first choice, and returns the resulting status
+    pub fn step(&mut self) -> Status{
+        self.step_choice(0)
+    }
+
+    /// performs the transition at `choice` in [`Self::choices`], returning the resulting status.
+    /// a choice that doesn't exist leaves the simulation as it is
+    pub fn step_choice(&mut self, choice: usize) -> Status{
+        let symbols = self.read();
+        self.status = self.machine.status(&self.state, &symbols);
+        if self.status != Status::Running{
+            return self.status;
+        }
+
+        let Some(transition) = self.machine.choices(&self.state, &symbols).get(choice).cloned() else{
+            return self.status;
+        };
+        if self.history.len() == HISTORY_LIMIT{
+            self.history.pop_front();
+        }
+        self.history.push_back(StepRecord{
+            heads: self.tracks.iter().map(|track| track.head).collect(),
+            state: self.state.clone(),
+            symbols,
+        });
+        apply(&mut self.tracks, &transition);
+        self.state = transition.next_state;
+        self.steps += 1;
+        self.update_status();
+        self.status
+    }
+
+    /// the states the last step went from and to, if it can still be undone
+    pub fn last_step(&self) -> Option<(&str, &str)>{
+        self.history.back().map(|record| (record.state.as_str(), self.state.as_str()))
+    }
+
+    /// undoes the last step, returning false if there is nothing to undo
+    pub fn step_back(&mut self) -> bool{
+        let Some(record) = self.history.pop_back() else{
+            return false;
+        };
+        for ((track, head), symbol) in self.tracks.iter_mut().zip(record.heads).zip(record.symbols){
+            track.head = head;
+            track.write(symbol);
+        }
+        self.state = record.state;
+        self.status = Status::Running;
+        self.steps -= 1;
+        true
+    }
+
+    /// steps until the machine halts or `max_steps` transitions have been taken
+    pub fn run(&mut self, max_steps: u64) -> Status{
+        for _ in 0..max_steps{
+            if self.step() != Status::Running{
+                break;
+            }
+        }
+        self.status
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// replaces 0s with 1s going right, accepting at the first blank
+    fn ones() -> Machine{
+        Machine::parse("q0 0 q0 1 R\nq0 1 q0 1 R\nq0 _ accept _ S").unwrap()
+    }
+
+    #[test]
+    fn steps_until_accepting(){
+        let mut simulation = Simulation::new(vec![Tape::from_cells("010")], ones());
+        assert_eq!(simulation.run(100), Status::Accepted);
+        assert_eq!(simulation.steps, 4);
+        assert_eq!(simulation.tracks[0].head, 3);
+        assert_eq!(simulation.tracks[0].tape.iter().map(|(_, c)| c).collect::<String>(), "111");
+    }
+
+    #[test]
+    fn stepping_back_undoes_the_write_move_and_state(){
+        let mut simulation = Simulation::new(vec![Tape::from_cells("0")], ones());
+        simulation.step();
+        assert_eq!(simulation.last_step(), Some(("q0", "q0")));
+        simulation.step();
+        assert_eq!(simulation.status, Status::Accepted);
+        assert_eq!(simulation.last_step(), Some(("q0", "accept")));
+
+        assert!(simulation.step_back());
+        assert_eq!(simulation.state, "q0");
+        assert_eq!(simulation.status, Status::Running);
+        assert_eq!(simulation.steps, 1);
+        assert!(simulation.step_back());
+        assert_eq!(simulation.tracks[0].head, 0);
+        assert_eq!(simulation.tracks[0].read(), '0');
+        assert_eq!(simulation.steps, 0);
+        assert!(!simulation.step_back());
+        assert_eq!(simulation.last_step(), None);
+    }
+
+    #[test]
+    fn history_is_capped(){
+        let machine = Machine::parse("q0 _ q0 _ R").unwrap();
+        let mut simulation = Simulation::new(vec![Tape::default()], machine);
+        simulation.run(HISTORY_LIMIT as u64 + 10);
+        assert_eq!(simulation.history.len(), HISTORY_LIMIT);
+        while simulation.step_back(){}
+        assert_eq!(simulation.steps, 10);
+        assert_eq!(simulation.tracks[0].head, 10);
+    }
+
+    #[test]
+    fn a_missing_transition_gets_stuck_without_changing_anything(){
+        let mut simulation = Simulation::new(vec![Tape::from_cells("2")], ones());
+        assert_eq!(simulation.step(), Status::Stuck);
+        assert_eq!(simulation.steps, 0);
+        assert!(!simulation.step_back());
+    }
+
+    #[test]
+    fn tracks_follow_the_machines_tape_count(){
+        let machine = Machine::parse("q0 0_ accept 00 RS").unwrap();
+        let mut simulation = Simulation::new(vec![Tape::from_cells("0")], machine);
+        assert_eq!(simulation.tracks.len(), 2);
+        assert_eq!(simulation.read(), vec!['0', '_']);
+        assert_eq!(simulation.step(), Status::Accepted);
+        assert_eq!(simulation.tracks[1].tape[0], '0');
+        assert_eq!(simulation.tracks[0].head, 1);
+        assert_eq!(simulation.tracks[1].head, 0);
+
+        simulation.replace_machine(ones());
+        assert_eq!(simulation.tracks.len(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+//! managing the saves in a directory.
+//!
+//! each slot is a `<name>.sav` file, optionally with a `<name>.tm` program next to it.
+//! renaming, duplicating and deleting a slot takes its program along.
+
+use std::{fs, io, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use crate::{save::{self, program_path, Metadata, SaveError}, Simulation};
+
+pub const SAVE_EXTENSION: &str = "sav";
+/// number of cells shown in a preview
+const PREVIEW_LENGTH: usize = 16;
+const MAX_NAME_LENGTH: usize = 32;
+/// goes between a slot's name and the number of its copy
+const COPY_SUFFIX: &str = " copy ";
+/// name given to worlds made from something without a usable title
+const IMPORTED_NAME: &str = "imported";
+
+/// what a save browser needs to know about a slot without loading it into the game
+#[derive(Clone, Debug)]
+pub struct SaveSummary{
+    pub name: String,
+    /// seconds since the unix epoch
+    pub modified: u64,
+    /// the start of the tape, or why the save couldn't be read
+    pub preview: Result<String, String>,
+}
+
+/// path of the slot called `name` in `dir`
+pub fn slot_path(dir: &Path, name: &str) -> PathBuf{
+    //not with_extension, which would take anything after a dot in the name for an extension
+    dir.join(format!("{}.{}", name, SAVE_EXTENSION))
+}
+
+/// every save in `dir`, most recently modified first
+pub fn list(dir: &Path) -> Result<Vec<SaveSummary>, SaveError>{
+    let entries = match fs::read_dir(dir){
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(SaveError::Io{path: dir.to_path_buf(), source: e}),
+    };
+
+    let mut summaries = Vec::new();
+    for entry in entries.flatten(){
+        let path = entry.path();
+        if path.extension().is_none_or(|e| e != SAVE_EXTENSION){
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else{
+            continue;
+        };
+        summaries.push(summarize(&path, name));
+    }
+    summaries.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.name.cmp(&b.name)));
+    Ok(summaries)
+}
+
+fn summarize(path: &Path, name: &str) -> SaveSummary{
+    let file_modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    match save::read_world(path){
+        Ok((simulation, metadata)) => SaveSummary{
+            name: name.to_string(),
+            //saves migrated from the old format have no timestamps of their own
+            modified: if metadata.version == 0 {file_modified} else {metadata.modified},
+            preview: Ok(preview(&simulation)),
+        },
+        Err(e) => SaveSummary{
+            name: name.to_string(),
+            modified: file_modified,
+            preview: Err(e.to_string()),
+        },
+    }
+}
+
+/// the first few cells of the written part of the first tape, which holds the input
+pub fn preview(simulation: &Simulation) -> String{
+    let Some(tape) = simulation.tracks.first().map(|track| &track.tape) else{
+        return String::new();
+    };
+    let Some((first, last)) = tape.bounds() else{
+        return String::new();
+    };
+    let mut preview: String = (first..=last).take(PREVIEW_LENGTH).map(|i| tape[i]).collect();
+    if last - first + 1 > PREVIEW_LENGTH as i64{
+        preview.push('…');
+    }
+    preview
+}
+
+/// checks that `name` can be used as a file name on every platform
+pub fn validate_name(name: &str) -> Result<(), SaveError>{
+    let valid = !name.trim().is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+        && !name.starts_with(' ')
+        && !name.ends_with(' ');
+    if valid{
+        Ok(())
+    }else{
+        Err(SaveError::InvalidName(name.to_string()))
+    }
+}
+
+/// the first unused name of the form `<base><n>`
+pub fn unused_name(dir: &Path, base: &str) -> String{
+    (1..).map(|n| format!("{}{}", base, n))
+        .find(|name| !slot_path(dir, name).exists())
+        .expect("there is always an unused name")
+}
+
+/// creates an empty world in `dir`, returning its name
+pub fn create(dir: &Path, base: &str) -> Result<String, SaveError>{
+    fs::create_dir_all(dir).map_err(|e| SaveError::Io{path: dir.to_path_buf(), source: e})?;
+    let name = unused_name(dir, base);
+    save::write_world(&slot_path(dir, &name), &Simulation::default(), &mut Metadata::default())?;
+    Ok(name)
+}
+
+/// creates a world in `dir` holding `simulation`, returning its name.
+/// it is called `title` made into a valid name, with a number after it if that is taken
+pub fn create_from(dir: &Path, title: &str, simulation: &Simulation) -> Result<String, SaveError>{
+    fs::create_dir_all(dir).map_err(|e| SaveError::Io{path: dir.to_path_buf(), source: e})?;
+    //room for the number
+    let base: String = title.chars()
+        .filter(|&c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+        .take(MAX_NAME_LENGTH - 3)
+        .collect();
+    let base = match base.trim(){
+        "" => IMPORTED_NAME,
+        base => base,
+    };
+    let name = if slot_path(dir, base).exists() {unused_name(dir, &format!("{} ", base))} else {base.to_string()};
+    save::write_world(&slot_path(dir, &name), simulation, &mut Metadata::default())?;
+    Ok(name)
+}
+
+/// renames a slot and its program
+pub fn rename(dir: &Path, from: &str, to: &str) -> Result<(), SaveError>{
+    validate_name(to)?;
+    let (from_path, to_path) = (slot_path(dir, from), slot_path(dir, to));
+    if from == to{
+        return Ok(());
+    }
+    if to_path.exists(){
+        return Err(SaveError::AlreadyExists(to.to_string()));
+    }
+    fs::rename(&from_path, &to_path).map_err(|e| SaveError::io(&from_path, e))?;
+    move_program(&program_path(&from_path), &program_path(&to_path), false)
+}
+
+/// copies a slot and its program, returning the copy's name
+pub fn duplicate(dir: &Path, name: &str) -> Result<String, SaveError>{
+    let from_path = slot_path(dir, name);
+    //room for the suffix and number
+    let base: String = name.chars().take(MAX_NAME_LENGTH - COPY_SUFFIX.len() - 3).collect();
+    let copy = unused_name(dir, &format!("{}{}", base.trim_end(), COPY_SUFFIX));
+    let to_path = slot_path(dir, &copy);
+    //the copy gets its own timestamps
+    let (simulation, _) = save::read_world(&from_path)?;
+    save::write_world(&to_path, &simulation, &mut Metadata::default())?;
+    move_program(&program_path(&from_path), &program_path(&to_path), true)?;
+    Ok(copy)
+}
+
+/// deletes a slot and its program
+pub fn delete(dir: &Path, name: &str) -> Result<(), SaveError>{
+    let path = slot_path(dir, name);
+    fs::remove_file(&path).map_err(|e| SaveError::io(&path, e))?;
+    let program = program_path(&path);
+    match fs::remove_file(&program){
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SaveError::io(&program, e)),
+        _ => Ok(()),
+    }
+}
+
+/// moves or copies a program if there is one
+fn move_program(from: &Path, to: &Path, copy: bool) -> Result<(), SaveError>{
+    let result = if copy {fs::copy(from, to).map(|_| ())} else {fs::rename(from, to)};
+    match result{
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SaveError::io(from, e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// an empty directory of its own for each test
+    fn empty_dir(test: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("turing-core-slots-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn names_with_dots_open_their_own_file(){
+        let dir = empty_dir("dots");
+        save::write_world(&dir.join("a.b.sav"), &Simulation::default(), &mut Metadata::default()).unwrap();
+        save::write_world(&dir.join("a.sav"), &Simulation::default(), &mut Metadata::default()).unwrap();
+        let mut names: Vec<String> = list(&dir).unwrap().into_iter().map(|summary| summary.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["a".to_string(), "a.b".to_string()]);
+
+        assert_eq!(slot_path(&dir, "a.b"), dir.join("a.b.sav"));
+        delete(&dir, "a.b").unwrap();
+        assert!(dir.join("a.sav").exists());
+        assert!(!dir.join("a.b.sav").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copies_of_long_names_are_valid_names(){
+        let dir = empty_dir("copies");
+        let name = "x".repeat(MAX_NAME_LENGTH);
+        validate_name(&name).unwrap();
+        save::write_world(&slot_path(&dir, &name), &Simulation::default(), &mut Metadata::default()).unwrap();
+        let copy = duplicate(&dir, &name).unwrap();
+        validate_name(&copy).unwrap();
+        assert!(copy.ends_with(" copy 1"));
+        let second = duplicate(&dir, &name).unwrap();
+        assert!(second.ends_with(" copy 2"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn validates_names(){
+        for name in ["World 1", "my-machine_2", "ünïcode"]{
+            assert!(validate_name(name).is_ok(), "{} was rejected", name);
+        }
+        for name in ["", " ", " leading", "trailing ", "a/b", "a.b", &"x".repeat(MAX_NAME_LENGTH + 1)]{
+            assert!(matches!(validate_name(name), Err(SaveError::InvalidName(_))), "{} was accepted", name);
+        }
+    }
+}
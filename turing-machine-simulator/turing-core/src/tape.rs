@@ -0,0 +1,198 @@
+use std::{collections::BTreeMap, ops::Index};
+
+/// symbol of an empty cell, unless the machine's alphabet says otherwise
+pub const BLANK: char = '_';
+/// number of cells allocated at once
+const CHUNK_SIZE: i64 = 256;
+
+type Chunk = Box<[char; CHUNK_SIZE as usize]>;
+
+/// the cells of a turing machine's tape.
+///
+/// the tape is infinite in both directions. cells are stored in chunks that are only
+/// allocated once something is written to them, so memory grows with the visited part of the tape.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tape{
+    chunks: BTreeMap<i64, Chunk>,
+    /// symbol of an empty cell
+    blank: char,
+}
+
+impl Default for Tape{
+    fn default() -> Self {
+        Self::new(BLANK)
+    }
+}
+
+impl Tape{
+    /// an empty tape whose empty cells hold `blank`
+    pub fn new(blank: char) -> Self{
+        Self{
+            chunks: BTreeMap::new(),
+            blank,
+        }
+    }
+
+    pub fn blank(&self) -> char{
+        self.blank
+    }
+
+    /// changes the symbol of an empty cell, keeping the other cells.
+    /// cells that already held the new blank become empty
+    pub fn set_blank(&mut self, blank: char){
+        if blank == self.blank{
+            return;
+        }
+        let cells: Vec<(i64, char)> = self.iter().collect();
+        self.chunks.clear();
+        self.blank = blank;
+        for (index, symbol) in cells{
+            self.set(index, symbol);
+        }
+    }
+
+    /// the symbol at `index`
+    pub fn get(&self, index: i64) -> char{
+        self[index]
+    }
+
+    /// writes `symbol` at `index`
+    pub fn set(&mut self, index: i64, symbol: char){
+        let (chunk, offset) = split_index(index);
+        match self.chunks.get_mut(&chunk){
+            Some(cells) => cells[offset] = symbol,
+            //no need to allocate to store a blank
+            None if symbol == self.blank => (),
+            None => {
+                let mut cells = Box::new([self.blank; CHUNK_SIZE as usize]);
+                cells[offset] = symbol;
+                self.chunks.insert(chunk, cells);
+            },
+        }
+    }
+
+    /// whether every cell is blank
+    pub fn is_empty(&self) -> bool{
+        self.iter().next().is_none()
+    }
+
+    /// the non-blank cells from left to right, along with their indices
+    pub fn iter(&self) -> impl Iterator<Item = (i64, char)> + '_{
+        let blank = self.blank;
+        self.chunks.iter().flat_map(move |(&chunk, cells)| {
+            cells.iter().enumerate()
+                .filter(move |&(_, &c)| c != blank)
+                .map(move |(offset, &c)| (chunk * CHUNK_SIZE + offset as i64, c))
+        })
+    }
+
+    /// number of non-blank cells
+    pub fn non_blank_count(&self) -> usize{
+        self.iter().count()
+    }
+
+    /// indices of the leftmost and rightmost non-blank cells
+    pub fn bounds(&self) -> Option<(i64, i64)>{
+        let first = self.iter().next()?.0;
+        let last = self.chunks.iter().rev()
+            .find_map(|(&chunk, cells)| cells.iter().rposition(|&c| c != self.blank).map(|offset| chunk * CHUNK_SIZE + offset as i64))?;
+        Some((first, last))
+    }
+
+    /// sets every cell back to blank
+    pub fn clear(&mut self){
+        self.chunks.clear();
+    }
+}
+
+impl Index<i64> for Tape{
+    type Output = char;
+
+    fn index(&self, index: i64) -> &char{
+        let (chunk, offset) = split_index(index);
+        match self.chunks.get(&chunk){
+            Some(cells) => &cells[offset],
+            None => &self.blank,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Tape{
+    /// a tape holding `cells` from the origin
+    pub(crate) fn from_cells(cells: &str) -> Self{
+        let mut tape = Self::default();
+        for (i, c) in cells.chars().enumerate(){
+            tape.set(i as i64, c);
+        }
+        tape
+    }
+}
+
+/// splits a cell index into its chunk and the offset within that chunk
+fn split_index(index: i64) -> (i64, usize){
+    (index.div_euclid(CHUNK_SIZE), index.rem_euclid(CHUNK_SIZE) as usize)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn cells_either_side_of_a_chunk_boundary_are_separate(){
+        let mut tape = Tape::default();
+        tape.set(CHUNK_SIZE - 1, 'a');
+        tape.set(CHUNK_SIZE, 'b');
+        assert_eq!(tape.get(CHUNK_SIZE - 1), 'a');
+        assert_eq!(tape.get(CHUNK_SIZE), 'b');
+        assert_eq!(tape.get(CHUNK_SIZE + 1), BLANK);
+        assert_eq!(tape.chunks.len(), 2);
+        assert_eq!(tape.iter().collect::<Vec<_>>(), vec![(CHUNK_SIZE - 1, 'a'), (CHUNK_SIZE, 'b')]);
+    }
+
+    #[test]
+    fn negative_positions_are_to_the_left_of_the_origin(){
+        let mut tape = Tape::default();
+        tape.set(-1, 'x');
+        tape.set(-CHUNK_SIZE, 'y');
+        tape.set(-CHUNK_SIZE - 1, 'z');
+        tape.set(0, 'o');
+        assert_eq!(split_index(-1), (-1, CHUNK_SIZE as usize - 1));
+        assert_eq!(split_index(-CHUNK_SIZE), (-1, 0));
+        assert_eq!(tape[-1], 'x');
+        assert_eq!(tape[-CHUNK_SIZE], 'y');
+        assert_eq!(tape[-CHUNK_SIZE - 1], 'z');
+        assert_eq!(tape.bounds(), Some((-CHUNK_SIZE - 1, 0)));
+        assert_eq!(tape.iter().map(|(i, _)| i).collect::<Vec<_>>(), vec![-CHUNK_SIZE - 1, -CHUNK_SIZE, -1, 0]);
+    }
+
+    #[test]
+    fn writing_a_blank_to_an_empty_chunk_allocates_nothing(){
+        let mut tape = Tape::default();
+        tape.set(1000, BLANK);
+        assert!(tape.chunks.is_empty());
+        assert!(tape.is_empty());
+        assert_eq!(tape.bounds(), None);
+    }
+
+    #[test]
+    fn bounds_skip_blanks_at_the_ends_of_chunks(){
+        let mut tape = Tape::default();
+        tape.set(3, '1');
+        tape.set(CHUNK_SIZE * 2 + 5, '1');
+        tape.set(CHUNK_SIZE * 2 + 5, BLANK);
+        assert_eq!(tape.bounds(), Some((3, 3)));
+        assert_eq!(tape.non_blank_count(), 1);
+    }
+
+    #[test]
+    fn changing_the_blank_keeps_the_other_cells(){
+        let mut tape = Tape::default();
+        tape.set(-2, '1');
+        tape.set(0, '#');
+        tape.set_blank('#');
+        assert_eq!(tape.blank(), '#');
+        assert_eq!(tape.iter().collect::<Vec<_>>(), vec![(-2, '1')]);
+        assert_eq!(tape[5], '#');
+    }
+}